@@ -0,0 +1,54 @@
+use std::{env, fs, io, io::Read, process, time::Instant};
+
+use duckchess_common::{Board, GameStart};
+use rocket::serde::json::serde_json;
+
+// runs perft from a position given as fen, or as GameStart or Board json.
+// usage: perft <depth> [fen or position file], the position is read from stdin if neither is given
+fn main() {
+	let args = env::args().collect::<Vec<String>>();
+	let depth: u32 = match args.get(1).map(|d| d.parse()) {
+		Some(Ok(depth)) => depth,
+		_ => {
+			eprintln!("usage: perft <depth> [fen or position file]");
+			process::exit(1);
+		}
+	};
+	let position = match args.get(2) {
+		Some(arg) if Board::from_fen(arg).is_ok() => arg.clone(),
+		Some(path) => fs::read_to_string(path).expect("failed to read position file"),
+		None => {
+			let mut position = String::new();
+			io::stdin()
+				.read_to_string(&mut position)
+				.expect("failed to read position from stdin");
+			position
+		}
+	};
+	let mut board = if let Ok(board) = Board::from_fen(&position) {
+		board
+	} else if let Ok(game_start) = serde_json::from_str::<GameStart>(&position) {
+		Board::new(game_start)
+	} else {
+		serde_json::from_str::<Board>(&position)
+			.expect("position is neither fen, a game start nor a board")
+	};
+	let start = Instant::now();
+	let divided = board.perft_divide(depth);
+	let elapsed = start.elapsed();
+	let mut total = 0;
+	for (mov, nodes) in divided {
+		println!(
+			"({}, {}) -> ({}, {}) {:?}: {}",
+			mov.from.0, mov.from.1, mov.to.0, mov.to.1, mov.move_type, nodes
+		);
+		total += nodes;
+	}
+	println!();
+	println!("nodes: {}", total);
+	println!(
+		"time: {:.3}s ({:.0} nodes/s)",
+		elapsed.as_secs_f64(),
+		total as f64 / elapsed.as_secs_f64()
+	);
+}
@@ -0,0 +1,124 @@
+use std::{
+	ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not},
+	sync::LazyLock,
+};
+
+use crate::{CAMEL_OFFSETS, KING_OFFSETS, KNIGHT_OFFSETS, MAX_BOARD_HEIGHT, MAX_BOARD_WIDTH, Vec2};
+
+const SQUARES: usize = MAX_BOARD_WIDTH as usize * MAX_BOARD_HEIGHT as usize;
+
+// one bit per square of the largest possible board, bit index = y * MAX_BOARD_WIDTH + x.
+// smaller boards only use some of the bits, see Tiles::inside
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct Bitboard(pub u128);
+
+impl Bitboard {
+	pub const EMPTY: Bitboard = Bitboard(0);
+	pub const FULL: Bitboard = Bitboard(u128::MAX);
+
+	fn index(pos: Vec2) -> u32 {
+		(pos.1 as u32) * MAX_BOARD_WIDTH as u32 + pos.0 as u32
+	}
+	pub fn square(pos: Vec2) -> Bitboard {
+		debug_assert!(pos.is_inside_board());
+		Bitboard(1 << Self::index(pos))
+	}
+	pub fn contains(self, pos: Vec2) -> bool {
+		pos.is_inside_board() && self.0 & (1 << Self::index(pos)) != 0
+	}
+	pub fn set(&mut self, pos: Vec2) {
+		*self |= Self::square(pos);
+	}
+	pub fn clear(&mut self, pos: Vec2) {
+		*self &= !Self::square(pos);
+	}
+	pub fn is_empty(self) -> bool {
+		self.0 == 0
+	}
+	pub fn count(self) -> u32 {
+		self.0.count_ones()
+	}
+	// squares in ascending bit order, so row by row from the top left
+	pub fn squares(self) -> impl Iterator<Item = Vec2> {
+		let mut bits = self.0;
+		std::iter::from_fn(move || {
+			if bits == 0 {
+				return None;
+			}
+			let index = bits.trailing_zeros() as i8;
+			bits &= bits - 1;
+			Some(Vec2(index % MAX_BOARD_WIDTH, index / MAX_BOARD_WIDTH))
+		})
+	}
+	fn from_offsets(pos: Vec2, offsets: &[Vec2]) -> Bitboard {
+		let mut bitboard = Bitboard::EMPTY;
+		for offset in offsets {
+			let to = pos + *offset;
+			if to.is_inside_board() {
+				bitboard.set(to);
+			}
+		}
+		bitboard
+	}
+	pub fn knight_attacks(pos: Vec2) -> Bitboard {
+		KNIGHT_ATTACKS[Self::index(pos) as usize]
+	}
+	pub fn king_attacks(pos: Vec2) -> Bitboard {
+		KING_ATTACKS[Self::index(pos) as usize]
+	}
+	pub fn camel_attacks(pos: Vec2) -> Bitboard {
+		CAMEL_ATTACKS[Self::index(pos) as usize]
+	}
+}
+
+// the tables are for the largest board, so they can include squares past the edge of a smaller one
+fn leaper_table(offsets: &[Vec2]) -> [Bitboard; SQUARES] {
+	let mut table = [Bitboard::EMPTY; SQUARES];
+	for (i, attacks) in table.iter_mut().enumerate() {
+		let pos = Vec2(i as i8 % MAX_BOARD_WIDTH, i as i8 / MAX_BOARD_WIDTH);
+		*attacks = Bitboard::from_offsets(pos, offsets);
+	}
+	table
+}
+
+static KNIGHT_ATTACKS: LazyLock<[Bitboard; SQUARES]> =
+	LazyLock::new(|| leaper_table(&KNIGHT_OFFSETS));
+static KING_ATTACKS: LazyLock<[Bitboard; SQUARES]> = LazyLock::new(|| leaper_table(&KING_OFFSETS));
+static CAMEL_ATTACKS: LazyLock<[Bitboard; SQUARES]> =
+	LazyLock::new(|| leaper_table(&CAMEL_OFFSETS));
+
+impl BitOr for Bitboard {
+	type Output = Bitboard;
+
+	fn bitor(self, rhs: Bitboard) -> Self::Output {
+		Bitboard(self.0 | rhs.0)
+	}
+}
+
+impl BitAnd for Bitboard {
+	type Output = Bitboard;
+
+	fn bitand(self, rhs: Bitboard) -> Self::Output {
+		Bitboard(self.0 & rhs.0)
+	}
+}
+
+impl BitOrAssign for Bitboard {
+	fn bitor_assign(&mut self, rhs: Bitboard) {
+		self.0 |= rhs.0;
+	}
+}
+
+impl BitAndAssign for Bitboard {
+	fn bitand_assign(&mut self, rhs: Bitboard) {
+		self.0 &= rhs.0;
+	}
+}
+
+impl Not for Bitboard {
+	type Output = Bitboard;
+
+	fn not(self) -> Self::Output {
+		Bitboard(!self.0)
+	}
+}
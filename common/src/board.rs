@@ -1,12 +1,17 @@
-use std::ops::Not;
+use std::{collections::HashMap, ops::Not};
 
 use rocket::serde;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{
-	SetupPieceType,
-	piece::{Piece, PieceType},
-	vec2::Vec2,
+	BoardMap, CustomPiece, GameOutcome, SetupPieceType, WinReason,
+	bitboard::Bitboard,
+	notation::PlayedMove,
+	piece::{DIAGONAL_DIRECTIONS, ORTHOGONAL_DIRECTIONS, PIECE_KINDS, Piece, PieceKind, PieceType},
+	ruleset::Ruleset,
+	variant::{TurnPhase, Variant},
+	vec2::{MAX_BOARD_HEIGHT, MAX_BOARD_WIDTH, Vec2},
+	zobrist,
 };
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
@@ -31,7 +36,7 @@ impl Not for Player {
 	}
 }
 
-#[derive(Serialize, Clone, Debug, Deserialize)]
+#[derive(Serialize, Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
 #[serde(
 	crate = "rocket::serde",
 	rename_all_fields = "camelCase",
@@ -40,6 +45,12 @@ impl Not for Player {
 pub enum Floor {
 	Light,
 	Dark,
+	// nothing can move onto or through a wall
+	Wall,
+	// a piece that lands in a hole is gone
+	Hole,
+	// sliding pieces cant stop on ice and keep going
+	Ice,
 }
 
 #[derive(Serialize, Clone, Debug, Deserialize)]
@@ -49,6 +60,165 @@ pub struct Tile {
 	pub piece: Option<Piece>,
 }
 
+// the tiles of a board, along with bitboards that mirror where the pieces and terrain are.
+// the board can be any size up to MAX_BOARD_WIDTH by MAX_BOARD_HEIGHT.
+// serializes as just the tiles
+#[derive(Clone, Debug)]
+pub struct Tiles {
+	tiles: Vec<Vec<Tile>>,
+	// every square of the board
+	inside: Bitboard,
+	players: [Bitboard; 2],
+	kinds: [Bitboard; PIECE_KINDS],
+	walls: Bitboard,
+	holes: Bitboard,
+	ice: Bitboard,
+}
+
+impl Tiles {
+	pub fn get(&self, pos: Vec2) -> &Tile {
+		&self.tiles[pos.1 as usize][pos.0 as usize]
+	}
+	// returns the piece that was replaced
+	fn set_piece(&mut self, pos: Vec2, piece: Option<Piece>) -> Option<Piece> {
+		if let Some(old) = &self.tiles[pos.1 as usize][pos.0 as usize].piece {
+			let (owner, kind) = (old.owner, old.piece_type.kind());
+			self.players[player_index(owner)].clear(pos);
+			self.kinds[kind as usize].clear(pos);
+		}
+		if let Some(new) = &piece {
+			self.players[player_index(new.owner)].set(pos);
+			self.kinds[new.piece_type.kind() as usize].set(pos);
+		}
+		std::mem::replace(&mut self.tiles[pos.1 as usize][pos.0 as usize].piece, piece)
+	}
+	pub fn set_floor(&mut self, pos: Vec2, floor: Floor) {
+		for terrain in [&mut self.walls, &mut self.holes, &mut self.ice] {
+			terrain.clear(pos);
+		}
+		match floor {
+			Floor::Wall => self.walls.set(pos),
+			Floor::Hole => self.holes.set(pos),
+			Floor::Ice => self.ice.set(pos),
+			Floor::Light | Floor::Dark => {}
+		}
+		self.tiles[pos.1 as usize][pos.0 as usize].floor = floor;
+	}
+	pub fn rows(&self) -> &[Vec<Tile>] {
+		&self.tiles
+	}
+	pub fn width(&self) -> i8 {
+		self.tiles[0].len() as i8
+	}
+	pub fn height(&self) -> i8 {
+		self.tiles.len() as i8
+	}
+	pub fn is_inside(&self, pos: Vec2) -> bool {
+		self.inside.contains(pos)
+	}
+	pub fn inside(&self) -> Bitboard {
+		self.inside
+	}
+	// rows have to be the same length, and the board has to fit in a bitboard
+	pub fn is_valid_size(width: usize, height: usize) -> bool {
+		(1..=MAX_BOARD_WIDTH as usize).contains(&width)
+			&& (1..=MAX_BOARD_HEIGHT as usize).contains(&height)
+	}
+	pub fn occupied(&self) -> Bitboard {
+		self.players[0] | self.players[1]
+	}
+	pub fn pieces(&self, player: Player) -> Bitboard {
+		self.players[player_index(player)]
+	}
+	pub fn kind(&self, kind: PieceKind) -> Bitboard {
+		self.kinds[kind as usize]
+	}
+	pub fn walls(&self) -> Bitboard {
+		self.walls
+	}
+	pub fn holes(&self) -> Bitboard {
+		self.holes
+	}
+	pub fn ice(&self) -> Bitboard {
+		self.ice
+	}
+}
+
+// the rows need to be a valid size, see Tiles::is_valid_size
+impl From<Vec<Vec<Tile>>> for Tiles {
+	fn from(tiles: Vec<Vec<Tile>>) -> Self {
+		debug_assert!(
+			Tiles::is_valid_size(tiles[0].len(), tiles.len())
+				&& tiles.iter().all(|row| row.len() == tiles[0].len())
+		);
+		let mut inside = Bitboard::EMPTY;
+		let mut players = [Bitboard::EMPTY; 2];
+		let mut kinds = [Bitboard::EMPTY; PIECE_KINDS];
+		let (mut walls, mut holes, mut ice) = (Bitboard::EMPTY, Bitboard::EMPTY, Bitboard::EMPTY);
+		for (y, row) in tiles.iter().enumerate() {
+			for (x, tile) in row.iter().enumerate() {
+				let pos = Vec2(x as i8, y as i8);
+				inside.set(pos);
+				if let Some(piece) = &tile.piece {
+					players[player_index(piece.owner)].set(pos);
+					kinds[piece.piece_type.kind() as usize].set(pos);
+				}
+				match tile.floor {
+					Floor::Wall => walls.set(pos),
+					Floor::Hole => holes.set(pos),
+					Floor::Ice => ice.set(pos),
+					Floor::Light | Floor::Dark => {}
+				}
+			}
+		}
+		Tiles {
+			tiles,
+			inside,
+			players,
+			kinds,
+			walls,
+			holes,
+			ice,
+		}
+	}
+}
+
+impl Serialize for Tiles {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		self.tiles.serialize(serializer)
+	}
+}
+
+impl<'de> Deserialize<'de> for Tiles {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let tiles = Vec::<Vec<Tile>>::deserialize(deserializer)?;
+		match tiles.first() {
+			Some(row)
+				if Tiles::is_valid_size(row.len(), tiles.len())
+					&& tiles.iter().all(|r| r.len() == row.len()) =>
+			{
+				Ok(Tiles::from(tiles))
+			}
+			_ => Err(serde::de::Error::custom("invalid board size")),
+		}
+	}
+}
+
+fn player_index(player: Player) -> usize {
+	match player {
+		Player::White => 0,
+		Player::Black => 1,
+	}
+}
+
+// how many of `piece_type` are in the reserve
+fn reserve_count(reserve: &[PieceType], piece_type: &PieceType) -> usize {
+	reserve
+		.iter()
+		.filter(|p| p.is_same_piece(piece_type))
+		.count()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(crate = "rocket::serde", rename_all = "camelCase", tag = "type")]
 pub struct Move {
@@ -70,18 +240,72 @@ pub enum MoveType {
 	EnPassant,
 	Promotion { into: PieceType },
 	Castle { from: Vec2, to: Vec2 },
+	// the piece on `to` is taken off the board, from is the same square
+	Removal,
+	// a piece from the player's reserve is put on `to`, from is Vec2::OFF_BOARD
+	Drop { piece: PieceType },
 }
 
 impl Move {
-	pub fn would_cause_lose(&self, board: &Board) -> bool {
-		// probably a more efficient way to do this
-		let mut board = board.clone();
-		board.do_move(self);
-		board.post_turn();
-		return board.about_to_win();
+	pub fn would_cause_lose(&self, board: &mut Board) -> bool {
+		let player = board.turn;
+		let undo = board.make_move(self);
+		let lose = !board.get_king_position(player).is_inside_board() || board.is_in_check(player);
+		board.unmake_move(undo);
+		lose
+	}
+	// the simple moves this move is made of, in the order they are applied.
+	// this is also what gets sent to clients
+	pub fn expand(&self) -> Vec<Move> {
+		match self.move_type {
+			MoveType::EnPassant => vec![
+				Move {
+					move_type: MoveType::JumpingMove,
+					from: Vec2(self.to.0, self.from.1),
+					to: self.to,
+				},
+				self.clone(),
+			],
+			MoveType::Castle { from, to } => vec![
+				Move {
+					move_type: MoveType::SlidingMove,
+					from,
+					to,
+				},
+				self.clone(),
+			],
+			_ => vec![self.clone()],
+		}
 	}
 }
 
+// everything needed to take back a move made with Board::make_move
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct MoveUndo {
+	turn: Player,
+	phase: TurnPhase,
+	duck: Option<Vec2>,
+	kings: [Vec2; 2],
+	hash: u64,
+	halfmove_clock: u32,
+	fullmove_number: u32,
+	checks: [u8; 2],
+	reserves: [Vec<PieceType>; 2],
+	// what every square the move touched held beforehand, in the order they were touched
+	squares: Vec<(Vec2, Option<Piece>)>,
+	pub king_captured: bool,
+}
+
+// a turn played with Board::evaluate_turn, kept so it can be taken back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct TurnUndo {
+	undo: MoveUndo,
+	// the repetition counts from before the turn, if the turn cleared them
+	repetitions: Option<HashMap<u64, u32>>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(crate = "rocket::serde", rename_all = "camelCase", tag = "type")]
 pub struct Board {
@@ -89,10 +313,46 @@ pub struct Board {
 	pub turn: Player,
 	pub white_player: String,
 	pub black_player: String,
-	pub board: [[Tile; 8]; 8],
+	pub board: Tiles,
 	pub kings: [Vec2; 2],
 	pub move_pieces: Vec<Vec2>,
 	pub moves: Vec<Vec<Move>>,
+	// zobrist hash of the current position, kept up to date by set_piece and post_turn
+	#[serde(default)]
+	pub hash: u64,
+	// how many times each position hash has occurred since the last capture or pawn move
+	#[serde(default)]
+	pub repetitions: HashMap<u64, u32>,
+	// turns since the last capture or pawn move
+	#[serde(default)]
+	pub halfmove_clock: u32,
+	// starts at 1 and goes up after every black turn
+	#[serde(default = "get_default_fullmove_number")]
+	pub fullmove_number: u32,
+	// how many times each player has given check, only counted in three-check
+	#[serde(default)]
+	pub checks: [u8; 2],
+	// captured pieces each player can drop, only used in crazyhouse.
+	// promoted pieces go back as pawns
+	#[serde(default)]
+	pub reserves: [Vec<PieceType>; 2],
+	#[serde(default)]
+	pub history: Vec<PlayedMove>,
+	// one entry for each turn in history. it isnt serialized with the board, so clients never get it,
+	// the game service stores it on its own
+	#[serde(skip)]
+	pub takebacks: Vec<TurnUndo>,
+	#[serde(default)]
+	pub variant: Variant,
+	// the neutral duck, None until it is first placed
+	#[serde(default)]
+	pub duck: Option<Vec2>,
+	#[serde(default)]
+	pub phase: TurnPhase,
+}
+
+fn get_default_fullmove_number() -> u32 {
+	1
 }
 
 // movegen
@@ -100,30 +360,224 @@ impl Board {
 	pub fn generate_moves(&mut self, deep: bool) {
 		self.move_pieces = Vec::new();
 		self.moves = Vec::new();
-		for y in 0..8 {
-			for x in 0..8 {
-				let p = Vec2(x, y);
-				if let Some(piece) = &self.get_tile(p).piece {
-					let moves = piece.generate_moves(self, p, deep);
-					if moves.len() > 0 {
-						self.moves.push(moves);
-						self.move_pieces.push(p);
-					}
+		if self.phase == TurnPhase::Duck {
+			self.generate_duck_moves(deep);
+			return;
+		}
+		for p in self.board.pieces(self.turn).squares() {
+			if let Some(piece) = self.get_tile(p).piece.clone() {
+				let moves = piece.generate_moves(self, p, deep);
+				if !moves.is_empty() {
+					self.moves.push(moves);
+					self.move_pieces.push(p);
 				}
 			}
 		}
+		if self.rules().has_drops() {
+			self.generate_drops(deep);
+		}
 	}
-	pub fn about_to_win(&mut self) -> bool {
-		self.generate_moves(false);
-		self.moves
-			.iter()
-			.find(|moves| {
-				moves
-					.iter()
-					.find(|m| m.to == self.get_king_position(!self.turn))
-					.is_some()
+	// every piece in the reserve can go to any empty square, except pawns on the first and last rank
+	fn generate_drops(&mut self, deep: bool) {
+		let mut pieces = Vec::<&PieceType>::new();
+		for piece in self.get_reserve(self.turn) {
+			if !pieces.contains(&piece) {
+				pieces.push(piece);
+			}
+		}
+		let empty = self.placeable();
+		let last_rank = self.board.height() - 1;
+		let moves = pieces
+			.into_iter()
+			.flat_map(|piece| {
+				empty
+					.squares()
+					.filter(move |to| {
+						piece.kind() != PieceKind::Pawn || (to.1 != 0 && to.1 != last_rank)
+					})
+					.map(move |to| Move {
+						move_type: MoveType::Drop {
+							piece: piece.clone(),
+						},
+						from: Vec2::OFF_BOARD,
+						to,
+					})
+			})
+			.collect::<Vec<Move>>();
+		let moves = if deep {
+			let rules = self.rules();
+			moves
+				.into_iter()
+				.filter(|m| rules.is_legal(self, m))
+				.collect()
+		} else {
+			moves
+		};
+		if !moves.is_empty() {
+			self.move_pieces.push(Vec2::OFF_BOARD);
+			self.moves.push(moves);
+		}
+	}
+	// the neutral duck goes to any empty square, and comes from off the board the first time
+	fn generate_duck_moves(&mut self, deep: bool) {
+		let from = self.duck.unwrap_or(Vec2::OFF_BOARD);
+		let moves = self
+			.placeable()
+			.squares()
+			.map(|to| Move {
+				move_type: MoveType::JumpingMove,
+				from,
+				to,
 			})
-			.is_some()
+			.collect::<Vec<Move>>();
+		let moves = if deep {
+			let rules = self.rules();
+			moves
+				.into_iter()
+				.filter(|m| rules.is_legal(self, m))
+				.collect()
+		} else {
+			moves
+		};
+		if !moves.is_empty() {
+			self.move_pieces.push(from);
+			self.moves.push(moves);
+		}
+	}
+	pub fn rules(&self) -> &'static dyn Ruleset {
+		self.variant.ruleset()
+	}
+	// every square with a piece or the neutral duck on it
+	pub fn occupied(&self) -> Bitboard {
+		match self.duck {
+			Some(duck) => self.board.occupied() | Bitboard::square(duck),
+			None => self.board.occupied(),
+		}
+	}
+	// every square nothing can move onto or through
+	pub fn blocked(&self) -> Bitboard {
+		self.occupied() | self.board.walls()
+	}
+	// where ducks and dropped pieces can be put
+	pub fn placeable(&self) -> Bitboard {
+		self.board.inside() & !(self.blocked() | self.board.holes())
+	}
+	// can the player whose turn it is capture the other king
+	pub fn about_to_win(&self) -> bool {
+		self.is_square_attacked(self.get_king_position(!self.turn), self.turn)
+	}
+	pub fn is_in_check(&self, player: Player) -> bool {
+		self.is_square_attacked(self.get_king_position(player), !player)
+	}
+	// could one of `by`'s pieces capture on `pos`
+	pub fn is_square_attacked(&self, pos: Vec2, by: Player) -> bool {
+		!self.attackers(pos, by).is_empty()
+	}
+	// the pieces of `by` that could capture on `pos`
+	pub fn attackers(&self, pos: Vec2, by: Player) -> Bitboard {
+		if !self.board.is_inside(pos) {
+			return Bitboard::EMPTY;
+		}
+		let attackers = self.board.pieces(by);
+		let attackers_of_kind = |kind| self.board.kind(kind) & attackers;
+		let archbishops = attackers_of_kind(PieceKind::Archbishop);
+		let chancellors = attackers_of_kind(PieceKind::Chancellor);
+		let amazons = attackers_of_kind(PieceKind::Amazon);
+		let knights = attackers_of_kind(PieceKind::Knight) | archbishops | chancellors | amazons;
+		let mut found = (Bitboard::knight_attacks(pos) & knights)
+			| (Bitboard::king_attacks(pos) & attackers_of_kind(PieceKind::King))
+			| (Bitboard::camel_attacks(pos) & attackers_of_kind(PieceKind::Camel));
+		let pawns = attackers_of_kind(PieceKind::Pawn);
+		let behind = pos - Piece::pawn_direction(by);
+		for side in [Vec2(-1, 0), Vec2(1, 0)] {
+			if pawns.contains(behind + side) {
+				found.set(behind + side);
+			}
+		}
+		// pieces without a shortcut ask their movement
+		let others =
+			attackers_of_kind(PieceKind::Grasshopper) | attackers_of_kind(PieceKind::Custom);
+		for from in others.squares() {
+			if let Some(piece) = &self.get_tile(from).piece
+				&& piece
+					.piece_type
+					.movement()
+					.attacks(by, from, self)
+					.contains(pos)
+			{
+				found.set(from);
+			}
+		}
+		let queens = attackers_of_kind(PieceKind::Queen) | amazons;
+		let blocked = self.blocked();
+		for (directions, sliders) in [
+			(
+				ORTHOGONAL_DIRECTIONS,
+				attackers_of_kind(PieceKind::Castle) | chancellors | queens,
+			),
+			(
+				DIAGONAL_DIRECTIONS,
+				attackers_of_kind(PieceKind::Bishop) | archbishops | queens,
+			),
+		] {
+			if sliders.is_empty() {
+				continue;
+			}
+			for dir in directions {
+				let mut cur = pos + dir;
+				while self.board.is_inside(cur) {
+					if blocked.contains(cur) {
+						if sliders.contains(cur) {
+							found.set(cur);
+						}
+						break;
+					}
+					cur += &dir;
+				}
+			}
+		}
+		found
+	}
+	// every square `by` could capture on, whether or not anything is there.
+	// pawns attack diagonally forward, and ducks dont attack at all
+	pub fn attack_map(&self, by: Player) -> Bitboard {
+		let mut map = Bitboard::EMPTY;
+		for from in self.board.pieces(by).squares() {
+			let Some(piece) = &self.get_tile(from).piece else {
+				continue;
+			};
+			match piece.piece_type {
+				PieceType::Pawn { .. } => {
+					for side in [Vec2(-1, 0), Vec2(1, 0)] {
+						let to = from + Piece::pawn_direction(by) + side;
+						if self.board.is_inside(to) && !self.board.walls().contains(to) {
+							map.set(to);
+						}
+					}
+				}
+				_ => map |= piece.piece_type.movement().attacks(by, from, self),
+			}
+		}
+		map
+	}
+	// expects moves to already be generated for the player whose turn it is
+	pub fn outcome(&self) -> Option<GameOutcome> {
+		self.rules().outcome(self)
+	}
+	// true when neither player can ever checkmate the other.
+	// ducks cant attack, but they can block a king's escape squares,
+	// so a lone minor piece is only insufficient when there are no ducks around.
+	// camels count as minor pieces, the other fairy pieces are assumed to be able to mate
+	pub fn has_insufficient_material(&self) -> bool {
+		let kind = |kind| self.board.kind(kind);
+		let minor = kind(PieceKind::Bishop) | kind(PieceKind::Knight) | kind(PieceKind::Camel);
+		let harmless = kind(PieceKind::King) | kind(PieceKind::Duck) | minor;
+		if !(self.board.occupied() & !harmless).is_empty() {
+			return false;
+		}
+		let minor_pieces = minor.count();
+		minor_pieces == 0
+			|| (minor_pieces == 1 && kind(PieceKind::Duck).is_empty() && self.duck.is_none())
 	}
 }
 
@@ -131,84 +585,327 @@ impl Board {
 #[serde(crate = "rocket::serde", rename_all = "camelCase", tag = "type")]
 pub struct Turn {
 	pub game_id: String,
-	pub piece_idx: usize,
-	pub move_idx: usize,
+	pub selection: MoveSelection,
+}
+
+// how a player picked their move
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(
+	crate = "rocket::serde",
+	rename_all = "camelCase",
+	rename_all_fields = "camelCase",
+	tag = "type"
+)]
+pub enum MoveSelection {
+	// indices into Board::moves
+	Index {
+		piece_idx: usize,
+		move_idx: usize,
+	},
+	Coordinates {
+		from: Vec2,
+		to: Vec2,
+		promotion: Option<PieceType>,
+	},
+	// a piece from the reserve
+	Drop {
+		piece: PieceType,
+		to: Vec2,
+	},
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub enum TurnError {
+	// the indices dont point at a move
+	NoSuchMove,
+	// no legal move goes from `from` to `to`
+	IllegalMove,
+	// the move promotes, but no piece to promote into was given
+	MissingPromotion,
+	// a promotion was given that the move doesnt allow
+	InvalidPromotion,
 }
 
 // do moves
 impl Board {
-	pub fn evaluate_turn(&mut self, turn: &Turn) -> Option<(Vec<Move>, bool)> {
-		let in_move = self.moves.get(turn.piece_idx)?.get(turn.move_idx)?.clone();
-		let mut output_moves = vec![in_move.clone()];
-		let mut game_over = false;
-		match in_move.move_type {
-			MoveType::EnPassant => output_moves.insert(
-				0,
-				Move {
-					move_type: MoveType::JumpingMove,
-					from: Vec2(in_move.to.0, in_move.from.1),
-					to: in_move.to,
-				},
-			),
-			MoveType::Castle { from, to } => output_moves.insert(
-				0,
-				Move {
-					move_type: MoveType::SlidingMove,
-					from,
-					to,
-				},
-			),
-			_ => {}
+	pub fn resolve_move(&self, selection: &MoveSelection) -> Result<Move, TurnError> {
+		match selection {
+			MoveSelection::Index {
+				piece_idx,
+				move_idx,
+			} => self
+				.moves
+				.get(*piece_idx)
+				.and_then(|moves| moves.get(*move_idx))
+				.cloned()
+				.ok_or(TurnError::NoSuchMove),
+			MoveSelection::Drop { piece, to } => self
+				.move_pieces
+				.iter()
+				.position(|p| *p == Vec2::OFF_BOARD)
+				.and_then(|i| {
+					self.moves[i].iter().find(|m| {
+						m.to == *to
+							&& matches!(&m.move_type, MoveType::Drop { piece: p } if p.is_same_piece(piece))
+					})
+				})
+				.cloned()
+				.ok_or(TurnError::IllegalMove),
+			MoveSelection::Coordinates {
+				from,
+				to,
+				promotion,
+			} => {
+				let candidates: Vec<&Move> = match self.move_pieces.iter().position(|p| p == from) {
+					Some(i) => self.moves[i].iter().filter(|m| m.to == *to).collect(),
+					None => vec![],
+				};
+				if candidates.is_empty() {
+					return Err(TurnError::IllegalMove);
+				}
+				let is_promotion = candidates
+					.iter()
+					.any(|m| matches!(m.move_type, MoveType::Promotion { .. }));
+				match promotion {
+					None if is_promotion => Err(TurnError::MissingPromotion),
+					None => Ok(candidates[0].clone()),
+					Some(promotion) => candidates
+						.into_iter()
+						.find(|m| match &m.move_type {
+							MoveType::Promotion { into } => into == promotion,
+							_ => false,
+						})
+						.cloned()
+						.ok_or(TurnError::InvalidPromotion),
+				}
+			}
 		}
-		for move_ in output_moves.iter() {
-			game_over = game_over || self.do_move(move_);
+	}
+	pub fn evaluate_turn(
+		&mut self,
+		turn: &Turn,
+	) -> Result<(Vec<Move>, Option<GameOutcome>), TurnError> {
+		let in_move = self.resolve_move(&turn.selection)?;
+		let output_moves = self.expand_move(&in_move);
+		let player = self.turn;
+		let notation = self.notation(&in_move);
+		let undo = self.make_move(&in_move);
+		let king_captured = undo.king_captured;
+		// a king can also fall into a hole, which loses instead
+		let winner = match self.get_king_position(player).is_inside_board() {
+			true => player,
+			false => !player,
+		};
+		let mut repetitions = None;
+		if self.halfmove_clock == 0 {
+			// earlier positions can never occur again
+			repetitions = Some(std::mem::take(&mut self.repetitions));
 		}
-		self.post_turn();
+		*self.repetitions.entry(self.hash).or_insert(0) += 1;
 		self.generate_moves(true);
-		return Some((output_moves, game_over || self.moves.len() <= 0));
+		let outcome = if king_captured {
+			Some(GameOutcome::Win {
+				winner,
+				reason: WinReason::KingCapture,
+			})
+		} else if self.phase == TurnPhase::Duck {
+			// the turn isnt over until the duck is moved
+			None
+		} else {
+			self.outcome()
+		};
+		self.history.push(PlayedMove {
+			player,
+			notation: notation + self.notation_suffix(&outcome),
+			mov: in_move,
+		});
+		self.takebacks.push(TurnUndo { undo, repetitions });
+		Ok((output_moves, outcome))
+	}
+	// takes back the last turn played with evaluate_turn, returning it
+	pub fn takeback(&mut self) -> Option<PlayedMove> {
+		let TurnUndo { undo, repetitions } = self.takebacks.pop()?;
+		if let Some(count) = self.repetitions.get_mut(&self.hash) {
+			*count -= 1;
+			if *count == 0 {
+				self.repetitions.remove(&self.hash);
+			}
+		}
+		if let Some(repetitions) = repetitions {
+			self.repetitions = repetitions;
+		}
+		self.unmake_move(undo);
+		self.generate_moves(true);
+		self.history.pop()
+	}
+	// plays a move and passes the turn, without generating the next player's moves
+	pub fn make_move(&mut self, mov: &Move) -> MoveUndo {
+		let mut undo = MoveUndo {
+			turn: self.turn,
+			phase: self.phase,
+			duck: self.duck,
+			kings: self.kings,
+			hash: self.hash,
+			halfmove_clock: self.halfmove_clock,
+			fullmove_number: self.fullmove_number,
+			checks: self.checks,
+			reserves: self.reserves.clone(),
+			squares: Vec::with_capacity(4),
+			king_captured: false,
+		};
+		if self.phase == TurnPhase::Duck {
+			self.set_duck(mov.to);
+			self.phase = TurnPhase::Piece;
+			self.hash ^= zobrist::duck_phase_key();
+			self.post_turn();
+			return undo;
+		}
+		self.halfmove_clock += 1;
+		let castling_hash = self.castling_hash();
+		for move_ in self.expand_move(mov) {
+			// drops come from off the board
+			if move_.from.is_inside_board() {
+				undo.squares
+					.push((move_.from, self.get_tile(move_.from).piece.clone()));
+			}
+			undo.squares
+				.push((move_.to, self.get_tile(move_.to).piece.clone()));
+			undo.king_captured = self.do_move(&move_) || undo.king_captured;
+		}
+		self.rules().after_move(self, mov, &mut undo);
+		self.hash ^= castling_hash ^ self.castling_hash();
+		if self.rules().has_neutral_duck() {
+			self.phase = TurnPhase::Duck;
+			self.hash ^= zobrist::duck_phase_key();
+		} else {
+			self.post_turn();
+		}
+		undo
+	}
+	pub fn unmake_move(&mut self, undo: MoveUndo) {
+		// moving a piece doesnt end the turn when the duck still has to move
+		let passed_turn = undo.phase == TurnPhase::Duck || !self.rules().has_neutral_duck();
+		if passed_turn {
+			for pos in self.board.kind(PieceKind::Pawn).squares() {
+				if let Some(mut piece) = self.get_tile(pos).piece.clone() {
+					piece.unpost_turn();
+					self.board.set_piece(pos, Some(piece));
+				}
+			}
+		}
+		for (pos, piece) in undo.squares.into_iter().rev() {
+			self.board.set_piece(pos, piece);
+		}
+		self.turn = undo.turn;
+		self.phase = undo.phase;
+		self.duck = undo.duck;
+		self.kings = undo.kings;
+		self.hash = undo.hash;
+		self.halfmove_clock = undo.halfmove_clock;
+		self.fullmove_number = undo.fullmove_number;
+		self.checks = undo.checks;
+		self.reserves = undo.reserves;
+	}
+	// the simple moves of a move together with what the variant adds to it,
+	// needs to be called before the move is made
+	pub fn expand_move(&self, mov: &Move) -> Vec<Move> {
+		let mut moves = mov.expand();
+		moves.extend(self.rules().move_effects(self, mov));
+		moves
 	}
 	pub fn do_move(&mut self, mov: &Move) -> bool {
+		if let MoveType::Removal = mov.move_type {
+			let removed = self.get_tile(mov.to).piece.clone();
+			self.set_piece(mov.to, None);
+			return match removed {
+				Some(Piece {
+					piece_type: PieceType::King,
+					owner,
+					..
+				}) => {
+					self.kings[player_index(owner)] = Vec2::OFF_BOARD;
+					true
+				}
+				_ => false,
+			};
+		}
+		if let MoveType::Drop { piece } = &mov.move_type {
+			let owner = self.turn;
+			self.take_from_reserve(owner, piece);
+			let home_rank = match owner {
+				Player::White => self.board.height() - 2,
+				Player::Black => 1,
+			};
+			self.set_piece(
+				mov.to,
+				Some(Piece {
+					piece_type: piece.clone(),
+					owner,
+					// pawns dropped on their home rank can still double advance, but nothing can castle
+					has_moved: piece.kind() != PieceKind::Pawn || mov.to.1 != home_rank,
+					promoted: false,
+				}),
+			);
+			return false;
+		}
 		let start = mov.from;
 		let end = mov.to;
 		let mut piece = self.get_tile(start).piece.clone();
+		let is_pawn = matches!(
+			piece.as_ref().map(|p| &p.piece_type),
+			Some(PieceType::Pawn { .. })
+		);
+		let is_capture = self.get_tile(end).piece.is_some();
+		if start != end && (is_pawn || is_capture) {
+			self.halfmove_clock = 0;
+		}
+		if start != end
+			&& self.rules().has_drops()
+			&& let (Some(mover), Some(captured)) = (&piece, &self.get_tile(end).piece)
+			&& mover.owner != captured.owner
+		{
+			// promoted pieces go back as the pawn they were
+			let piece_type = if captured.promoted || captured.piece_type.kind() == PieceKind::Pawn {
+				PieceType::Pawn {
+					turns_since_double_advance: None,
+				}
+			} else {
+				captured.piece_type.clone()
+			};
+			self.add_to_reserve(mover.owner, piece_type);
+		}
 		match &mut piece {
 			Some(Piece {
 				piece_type: PieceType::Pawn {
 					turns_since_double_advance,
 				},
 				..
-			}) => {
-				if (start.1 - end.1).abs() > 1 {
-					*turns_since_double_advance = Some(0);
-				}
+			}) if (start.1 - end.1).abs() > 1 => {
+				*turns_since_double_advance = Some(0);
 			}
 			Some(Piece {
 				piece_type: PieceType::King,
 				owner,
 				..
 			}) => {
-				self.kings[match owner {
-					Player::White => 0,
-					Player::Black => 1,
-				}] = end;
+				self.kings[player_index(*owner)] = end;
 			}
 			_ => {}
 		}
-		if let Some(ref mut piece) = piece {
-			if start != end {
-				piece.has_moved = true;
-			}
+		if let Some(ref mut piece) = piece
+			&& start != end
+		{
+			piece.has_moved = true;
 		}
-		match &mov.move_type {
-			MoveType::Promotion { into } => {
-				if let Some(ref mut piece) = piece {
-					piece.piece_type = into.clone();
-				}
-			}
-			_ => {}
+		if let MoveType::Promotion { into } = &mov.move_type
+			&& let Some(ref mut piece) = piece
+		{
+			piece.piece_type = into.clone();
+			piece.promoted = true;
 		}
 		let game_over = if start != end {
-			self.get_tile_mut(start).piece = Default::default();
+			self.set_piece(start, None);
 			if let Some(Piece {
 				piece_type: PieceType::King,
 				..
@@ -223,18 +920,109 @@ impl Board {
 		} else {
 			false
 		};
-		self.get_tile_mut(end).piece = piece;
+		self.set_piece(end, piece);
+		if start != end && self.board.holes().contains(end) {
+			// the piece falls in
+			if let Some(Piece {
+				piece_type: PieceType::King,
+				owner,
+				..
+			}) = self.set_piece(end, None)
+			{
+				self.kings[player_index(owner)] = Vec2::OFF_BOARD;
+				return true;
+			}
+		}
 		game_over
 	}
 	fn post_turn(&mut self) {
-		for y in 0..8 {
-			for x in 0..8 {
-				if let Some(piece) = &mut self.get_tile_mut(Vec2(x, y)).piece {
-					piece.post_turn();
-				}
+		for pos in self.board.kind(PieceKind::Pawn).squares() {
+			if let Some(mut piece) = self.get_tile(pos).piece.clone() {
+				piece.post_turn();
+				self.set_piece(pos, Some(piece));
 			}
 		}
+		if self.turn == Player::Black {
+			self.fullmove_number += 1;
+		}
 		self.turn = !self.turn;
+		self.hash ^= zobrist::turn_key();
+	}
+	// sets a piece as part of a move, so unmake_move puts the old one back
+	pub fn replace_piece(&mut self, undo: &mut MoveUndo, pos: Vec2, piece: Option<Piece>) {
+		undo.squares.push((pos, self.get_tile(pos).piece.clone()));
+		self.set_piece(pos, piece);
+	}
+	pub fn add_check(&mut self, player: Player) {
+		let checks = &mut self.checks[player_index(player)];
+		self.hash ^= zobrist::checks_key(player, *checks);
+		*checks += 1;
+		self.hash ^= zobrist::checks_key(player, *checks);
+	}
+	pub fn get_checks(&self, player: Player) -> u8 {
+		self.checks[player_index(player)]
+	}
+	pub fn get_reserve(&self, player: Player) -> &[PieceType] {
+		&self.reserves[player_index(player)]
+	}
+	fn add_to_reserve(&mut self, player: Player, piece_type: PieceType) {
+		let count = reserve_count(self.get_reserve(player), &piece_type);
+		self.hash ^= zobrist::reserve_key(player, &piece_type, count + 1);
+		self.reserves[player_index(player)].push(piece_type);
+	}
+	fn take_from_reserve(&mut self, player: Player, piece_type: &PieceType) {
+		let count = reserve_count(self.get_reserve(player), piece_type);
+		self.hash ^= zobrist::reserve_key(player, piece_type, count);
+		let reserve = &mut self.reserves[player_index(player)];
+		if let Some(i) = reserve.iter().position(|p| p.is_same_piece(piece_type)) {
+			reserve.remove(i);
+		}
+	}
+	fn set_duck(&mut self, pos: Vec2) {
+		if let Some(old) = self.duck {
+			self.hash ^= zobrist::neutral_duck_key(old);
+		}
+		self.hash ^= zobrist::neutral_duck_key(pos);
+		self.duck = Some(pos);
+	}
+	// all changes to pieces go through here to keep the hash and bitboards up to date.
+	// returns the piece that was replaced
+	pub fn set_piece(&mut self, pos: Vec2, piece: Option<Piece>) -> Option<Piece> {
+		if let Some(new) = &piece {
+			self.hash ^= zobrist::piece_key(new, pos);
+		}
+		let old = self.board.set_piece(pos, piece);
+		if let Some(old) = &old {
+			self.hash ^= zobrist::piece_key(old, pos);
+		}
+		old
+	}
+	pub fn compute_hash(&self) -> u64 {
+		let mut hash = match self.turn {
+			Player::White => 0,
+			Player::Black => zobrist::turn_key(),
+		};
+		for pos in self.board.occupied().squares() {
+			if let Some(piece) = &self.get_tile(pos).piece {
+				hash ^= zobrist::piece_key(piece, pos);
+			}
+		}
+		hash ^= self.castling_hash();
+		if let Some(duck) = self.duck {
+			hash ^= zobrist::neutral_duck_key(duck);
+		}
+		if self.phase == TurnPhase::Duck {
+			hash ^= zobrist::duck_phase_key();
+		}
+		for player in [Player::White, Player::Black] {
+			hash ^= zobrist::checks_key(player, self.get_checks(player));
+			let reserve = self.get_reserve(player);
+			for (i, piece) in reserve.iter().enumerate() {
+				let count = reserve_count(&reserve[..=i], piece);
+				hash ^= zobrist::reserve_key(player, piece, count);
+			}
+		}
+		hash
 	}
 }
 
@@ -251,84 +1039,129 @@ impl Board {
 			Player::Black => &self.white_player,
 		}
 	}
-	fn find_king_position(board: &[[Tile; 8]; 8], player: Player) -> Vec2 {
-		board
-			.iter()
-			.enumerate()
-			.find_map(|(y, row)| {
-				let x = row.iter().position(|tile| match tile.piece {
-					Some(Piece {
-						piece_type: PieceType::King,
-						owner,
-						..
-					}) => owner == player,
-					_ => false,
-				});
-				x.map(|x| Vec2(x as i8, y as i8))
-			})
+	fn find_king_position(board: &Tiles, player: Player) -> Vec2 {
+		(board.kind(PieceKind::King) & board.pieces(player))
+			.squares()
+			.next()
 			.expect("already verified king exists")
 	}
 	pub fn get_king_position(&self, player: Player) -> Vec2 {
-		self.kings[match player {
-			Player::White => 0,
-			Player::Black => 1,
-		}]
+		self.kings[player_index(player)]
 	}
 	pub fn get_tile(&self, pos: Vec2) -> &Tile {
-		&self.board[pos.1 as usize][pos.0 as usize]
+		self.board.get(pos)
 	}
-	pub fn get_tile_mut(&mut self, pos: Vec2) -> &mut Tile {
-		&mut self.board[pos.1 as usize][pos.0 as usize]
+	// the unmoved castles on the first and last file next to an unmoved king,
+	// white's before black's and the last file first
+	pub fn castling_rights(&self) -> Vec<(Player, Vec2)> {
+		let mut rights = Vec::new();
+		for owner in [Player::White, Player::Black] {
+			let king = self.get_king_position(owner);
+			// atomic kings can be blown off the board
+			if !king.is_inside_board()
+				|| self
+					.get_tile(king)
+					.piece
+					.as_ref()
+					.is_none_or(|k| k.has_moved)
+			{
+				continue;
+			}
+			for file in [self.board.width() - 1, 0] {
+				let pos = Vec2(file, king.1);
+				if let Some(Piece {
+					piece_type: PieceType::Castle,
+					owner: castle_owner,
+					has_moved: false,
+					..
+				}) = &self.get_tile(pos).piece
+					&& *castle_owner == owner
+				{
+					rights.push((owner, pos));
+				}
+			}
+		}
+		rights
+	}
+	// castling rights are hashed as a whole, since a king moving takes them from both castles
+	fn castling_hash(&self) -> u64 {
+		self.castling_rights()
+			.into_iter()
+			.fold(0, |hash, (_, pos)| hash ^ zobrist::castling_key(pos))
 	}
 	pub fn new(mut game_start: GameStart) -> Self {
 		let game_id = game_start.game_id;
 		let white_player = game_start.white.id;
 		let black_player = game_start.black.id;
 		game_start.black.setup.rotate();
-		let board = (0..8)
-			.into_iter()
+		let (black, white) = (&game_start.black.setup.0, &game_start.white.setup.0);
+		let height = game_start.map.height() as usize;
+		// black's rows are at the top and white's at the bottom
+		let white_start = height - white.len();
+		let board = (0..height)
 			.map(|y| {
-				(0..8)
-					.into_iter()
+				let (row, owner) = if y < black.len() {
+					(Some(&black[y]), Player::Black)
+				} else if y >= white_start {
+					(Some(&white[y - white_start]), Player::White)
+				} else {
+					(None, Player::White)
+				};
+				(0..game_start.map.width() as usize)
 					.map(|x| Tile {
-						floor: if (y + x) % 2 == 0 {
-							Floor::Light
-						} else {
-							Floor::Dark
-						},
-						piece: if y < 2 {
-							game_start.black.setup.0[y][x].clone().map(|p| p.into())
-						} else if y >= 6 {
-							game_start.white.setup.0[y - 6][x].clone().map(|p| p.into())
-						} else {
-							None
-						}
-						.map(|piece_type| Piece {
-							piece_type,
-							has_moved: false,
-							owner: if y < 4 { Player::Black } else { Player::White },
-						}),
+						floor: game_start.map.floor(Vec2(x as i8, y as i8)),
+						piece: row.and_then(|row| row.get(x).cloned().flatten()).map(
+							|piece_type| Piece {
+								piece_type: piece_type.into(),
+								has_moved: false,
+								promoted: false,
+								owner,
+							},
+						),
 					})
-					.collect::<Vec<Tile>>()
-					.try_into()
-					.unwrap()
+					.collect()
 			})
-			.collect::<Vec<[Tile; 8]>>()
-			.try_into()
-			.unwrap();
+			.collect::<Vec<Vec<Tile>>>();
+		let board = Tiles::from(board);
+		let mut board = Self::from_tiles(board, Player::White, game_start.variant, None);
+		board.id = game_id;
+		board.white_player = white_player;
+		board.black_player = black_player;
+		board
+	}
+	// a fresh board with no players, the tiles must have one king for each player
+	pub(crate) fn from_tiles(
+		board: Tiles,
+		turn: Player,
+		variant: Variant,
+		duck: Option<Vec2>,
+	) -> Self {
 		let mut board = Self {
-			turn: Player::White,
-			white_player,
-			black_player,
+			turn,
+			white_player: String::new(),
+			black_player: String::new(),
 			move_pieces: Default::default(),
 			moves: Default::default(),
 			kings: [
 				Self::find_king_position(&board, Player::White),
 				Self::find_king_position(&board, Player::Black),
 			],
-			id: game_id,
+			id: String::new(),
 			board,
+			hash: 0,
+			repetitions: Default::default(),
+			halfmove_clock: 0,
+			fullmove_number: 1,
+			checks: [0, 0],
+			reserves: Default::default(),
+			history: Vec::new(),
+			takebacks: Vec::new(),
+			variant,
+			duck,
+			phase: TurnPhase::Piece,
 		};
+		board.hash = board.compute_hash();
+		board.repetitions.insert(board.hash, 1);
 		board.generate_moves(true);
 		board
 	}
@@ -336,14 +1169,25 @@ impl Board {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(crate = "rocket::serde", rename_all = "camelCase")]
-pub struct BoardSetup([[Option<SetupPieceType>; 8]; 2]);
+pub struct BoardSetup(Vec<Vec<Option<SetupPieceType>>>);
 
 impl BoardSetup {
+	// rows go from the front rank to the back rank, left to right from the player's side.
+	// every row has to be as wide as the board
+	pub fn new(rows: Vec<Vec<Option<SetupPieceType>>>) -> Self {
+		BoardSetup(rows)
+	}
+	pub fn rows(&self) -> &[Vec<Option<SetupPieceType>>] {
+		&self.0
+	}
+	pub fn width(&self) -> usize {
+		self.0.first().map_or(0, |row| row.len())
+	}
 	// when playing as black, we rotate the board setup
 	pub fn rotate(&mut self) {
 		// horizontal mirror
-		for y in 0..2 {
-			self.0[y].reverse();
+		for row in &mut self.0 {
+			row.reverse();
 		}
 		// vertical mirror
 		self.0.reverse();
@@ -357,6 +1201,13 @@ impl BoardSetup {
 		}
 		sum
 	}
+	// custom pieces the server doesnt know about
+	pub fn has_unknown_pieces(&self) -> bool {
+		self.0.iter().flatten().flatten().any(|piece| match piece {
+			SetupPieceType::Custom { name } => CustomPiece::find(name).is_none(),
+			_ => false,
+		})
+	}
 	fn count_kings(&self) -> i32 {
 		let mut count = 0;
 		for row in &self.0 {
@@ -366,12 +1217,29 @@ impl BoardSetup {
 				}
 			}
 		}
-		return count;
+		count
 	}
-	// standard setup + 500 (for fun)
+	// standard setup + 500 (for fun), for every 8 files
 	const MAX_TOTAL_VALUE: i32 = 4800;
+	// one or two rows of the same width
 	pub fn is_valid(&self) -> bool {
-		self.total_value() <= Self::MAX_TOTAL_VALUE && self.count_kings() == 1
+		(1..=2).contains(&self.0.len())
+			&& self.0.iter().all(|row| row.len() == self.width())
+			&& self.total_value() <= Self::MAX_TOTAL_VALUE * self.width() as i32 / 8
+			&& self.count_kings() == 1
+			&& !self.has_unknown_pieces()
+	}
+	// the setup has to fill the width of the map and leave room between the two sides
+	pub fn is_valid_for(&self, variant: Variant, map: &BoardMap) -> bool {
+		self.is_valid()
+			&& self.width() == map.width() as usize
+			&& self.0.len() * 2 < map.height() as usize
+			&& (variant.ruleset().allows_owned_ducks()
+				|| self
+					.0
+					.iter()
+					.flatten()
+					.all(|piece| !matches!(piece, Some(SetupPieceType::Duck))))
 	}
 }
 
@@ -381,6 +1249,10 @@ pub struct GameStart {
 	pub white: GameStartPlayer,
 	pub black: GameStartPlayer,
 	pub game_id: String,
+	#[serde(default)]
+	pub variant: Variant,
+	#[serde(default)]
+	pub map: BoardMap,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -23,6 +23,12 @@ pub enum Timer {
 }
 
 pub const GAME_LENGTH: u64 = 2 * 10 * 60;
+impl Default for ChessClock {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 impl ChessClock {
 	pub fn new() -> ChessClock {
 		ChessClock {
@@ -36,6 +42,46 @@ impl ChessClock {
 			Player::Black => &mut self.black,
 		}
 	}
+	pub fn time_remaining(&self, player: Player) -> u64 {
+		match player {
+			Player::White => self.white.time_remaining(),
+			Player::Black => self.black.time_remaining(),
+		}
+	}
+	fn times(&self) -> [u64; 2] {
+		[
+			self.time_remaining(Player::White),
+			self.time_remaining(Player::Black),
+		]
+	}
+}
+
+// the time both players had left at the start of every turn that can still be taken back,
+// kept by the game service next to the board's undo stack
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct ClockHistory(Vec<[u64; 2]>);
+
+impl ClockHistory {
+	pub fn new(clock: &ChessClock) -> ClockHistory {
+		ClockHistory(vec![clock.times()])
+	}
+	// called after every turn, once the player who moved has stopped their timer
+	pub fn push(&mut self, clock: &ChessClock) {
+		self.0.push(clock.times());
+	}
+	// gives both players back the time they spent since the start of the turn `turns` turns ago.
+	// both timers are left paused, turn_start starts the right one again
+	pub fn take_back(&mut self, turns: usize, clock: &mut ChessClock) -> bool {
+		if turns >= self.0.len() {
+			return false;
+		}
+		self.0.truncate(self.0.len() - turns);
+		let [white, black] = self.0[self.0.len() - 1];
+		clock.white = Timer::new(white);
+		clock.black = Timer::new(black);
+		true
+	}
 }
 
 impl Timer {
@@ -76,18 +122,25 @@ impl Timer {
 			Timer::Paused { .. } => true,
 		}
 	}
+	// in seconds
+	pub fn time_remaining(&self) -> u64 {
+		match self {
+			Timer::Running { end_time } => end_time.saturating_sub(
+				SystemTime::now()
+					.duration_since(SystemTime::UNIX_EPOCH)
+					.expect("system time before unix epoch")
+					.as_secs(),
+			),
+			Timer::Paused { time_remaining } => *time_remaining,
+		}
+	}
 	pub fn has_time(&self) -> bool {
 		match self {
-			Timer::Running { end_time } => {
-				match SystemTime::UNIX_EPOCH
-					.checked_add(Duration::from_secs(*end_time))
-					.expect("u64 time overflow")
-					.duration_since(SystemTime::now())
-				{
-					Ok(_) => true,
-					Err(_) => false,
-				}
-			}
+			Timer::Running { end_time } => SystemTime::UNIX_EPOCH
+				.checked_add(Duration::from_secs(*end_time))
+				.expect("u64 time overflow")
+				.duration_since(SystemTime::now())
+				.is_ok(),
 			Timer::Paused { time_remaining } => *time_remaining > 0,
 		}
 	}
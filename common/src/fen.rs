@@ -0,0 +1,299 @@
+use std::fmt::{self, Display};
+
+use crate::{
+	Board, Floor, MAX_BOARD_WIDTH, Piece, PieceKind, PieceType, Player, Tile, Tiles, Variant, Vec2,
+};
+
+// fen-like text notation for positions.
+// pieces use the usual letters plus D for ducks, uppercase for white and lowercase for black.
+// the neutral duck of duck chess is a *, and a position with one is read as duck chess.
+// positions are always written as if the piece still has to move.
+// the last rank (y = 0) comes first, like in chess fen. the size of the board follows from the ranks.
+// castling rights are derived from unmoved kings and castles, K and Q mean the castle on the last and first file.
+// the en passant square is the one behind a pawn that just double advanced.
+// pawns on their two home ranks are treated as unmoved, since that can't be written down
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum FenError {
+	MissingField,
+	TooManyFields,
+	InvalidPlacement,
+	InvalidPiece(char),
+	InvalidTurn,
+	InvalidCastling,
+	InvalidEnPassant,
+	InvalidNumber,
+	// each player needs exactly one king
+	KingCount,
+}
+
+impl Display for FenError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			FenError::MissingField => f.write_str("missing field"),
+			FenError::TooManyFields => f.write_str("too many fields"),
+			FenError::InvalidPlacement => {
+				f.write_str("piece placement ranks must all have the same supported width")
+			}
+			FenError::InvalidPiece(c) => write!(f, "invalid piece '{}'", c),
+			FenError::InvalidTurn => f.write_str("side to move must be 'w' or 'b'"),
+			FenError::InvalidCastling => f.write_str("invalid castling rights"),
+			FenError::InvalidEnPassant => f.write_str("invalid en passant square"),
+			FenError::InvalidNumber => f.write_str("invalid move counter"),
+			FenError::KingCount => f.write_str("each player needs exactly one king"),
+		}
+	}
+}
+
+impl std::error::Error for FenError {}
+
+fn home_ranks(owner: Player, height: i8) -> [i8; 2] {
+	match owner {
+		Player::White => [height - 2, height - 1],
+		Player::Black => [0, 1],
+	}
+}
+
+fn castling_letter(owner: Player, file: i8) -> char {
+	let letter = if file == 0 { 'Q' } else { 'K' };
+	match owner {
+		Player::White => letter,
+		Player::Black => letter.to_ascii_lowercase(),
+	}
+}
+
+impl Board {
+	pub fn to_fen(&self) -> String {
+		let mut fen = String::new();
+		for (y, row) in self.board.rows().iter().enumerate() {
+			if y > 0 {
+				fen.push('/');
+			}
+			let mut empty = 0;
+			for (x, tile) in row.iter().enumerate() {
+				if self.duck == Some(Vec2(x as i8, y as i8)) {
+					if empty > 0 {
+						fen.push_str(&empty.to_string());
+						empty = 0;
+					}
+					fen.push('*');
+					continue;
+				}
+				match &tile.piece {
+					Some(piece) => {
+						if empty > 0 {
+							fen.push_str(&empty.to_string());
+							empty = 0;
+						}
+						let letter = piece.piece_type.letter();
+						fen.push(match piece.owner {
+							Player::White => letter,
+							Player::Black => letter.to_ascii_lowercase(),
+						});
+					}
+					None => empty += 1,
+				}
+			}
+			if empty > 0 {
+				fen.push_str(&empty.to_string());
+			}
+		}
+
+		fen.push_str(match self.turn {
+			Player::White => " w ",
+			Player::Black => " b ",
+		});
+
+		let height = self.board.height();
+		let mut castling = String::new();
+		for (owner, pos) in self.castling_rights() {
+			castling.push(castling_letter(owner, pos.0));
+		}
+		if castling.is_empty() {
+			castling.push('-');
+		}
+		fen.push_str(&castling);
+
+		let en_passant = self.board.kind(PieceKind::Pawn).squares().find_map(|pos| {
+			match &self.get_tile(pos).piece {
+				Some(Piece {
+					piece_type: PieceType::Pawn {
+						turns_since_double_advance: Some(1),
+					},
+					owner,
+					..
+				}) => Some(pos - Piece::pawn_direction(*owner)),
+				_ => None,
+			}
+		});
+		fen.push(' ');
+		fen.push_str(&en_passant.map_or("-".to_string(), |pos| pos.square_name(height)));
+
+		fen.push_str(&format!(
+			" {} {}",
+			self.halfmove_clock, self.fullmove_number
+		));
+		fen
+	}
+
+	// the move counters can be left out, and default to 0 and 1
+	pub fn from_fen(fen: &str) -> Result<Board, FenError> {
+		let mut fields = fen.split_whitespace();
+		let placement = fields.next().ok_or(FenError::MissingField)?;
+		let turn = match fields.next().ok_or(FenError::MissingField)? {
+			"w" => Player::White,
+			"b" => Player::Black,
+			_ => return Err(FenError::InvalidTurn),
+		};
+		let castling = fields.next().ok_or(FenError::MissingField)?;
+		let en_passant = fields.next().ok_or(FenError::MissingField)?;
+		let halfmove_clock = match fields.next() {
+			Some(n) => n.parse().map_err(|_| FenError::InvalidNumber)?,
+			None => 0,
+		};
+		let fullmove_number = match fields.next() {
+			Some(n) => n.parse().map_err(|_| FenError::InvalidNumber)?,
+			None => 1,
+		};
+		if fields.next().is_some() {
+			return Err(FenError::TooManyFields);
+		}
+
+		let ranks = placement.split('/').collect::<Vec<&str>>();
+		let height = ranks.len() as i8;
+		let empty_tile = |x: usize, y: usize| Tile {
+			floor: if (y + x).is_multiple_of(2) {
+				Floor::Light
+			} else {
+				Floor::Dark
+			},
+			piece: None,
+		};
+		let mut tiles: Vec<Vec<Tile>> = Vec::new();
+		let mut duck = None;
+		for (y, rank) in ranks.into_iter().enumerate() {
+			let mut row = Vec::new();
+			// runs of empty squares can have more than one digit on wide boards
+			let mut empty = 0;
+			for c in rank.chars() {
+				if let Some(digit) = c.to_digit(10) {
+					empty = empty * 10 + digit as usize;
+					if empty > MAX_BOARD_WIDTH as usize {
+						return Err(FenError::InvalidPlacement);
+					}
+					continue;
+				}
+				for _ in 0..std::mem::take(&mut empty) {
+					row.push(empty_tile(row.len(), y));
+				}
+				let x = row.len();
+				row.push(empty_tile(x, y));
+				if c == '*' && duck.is_none() {
+					duck = Some(Vec2(x as i8, y as i8));
+					continue;
+				}
+				let piece_type = PieceType::from_letter(c).ok_or(FenError::InvalidPiece(c))?;
+				let owner = if c.is_ascii_uppercase() {
+					Player::White
+				} else {
+					Player::Black
+				};
+				row[x].piece = Some(Piece {
+					has_moved: match piece_type {
+						PieceType::Pawn { .. } => !home_ranks(owner, height).contains(&(y as i8)),
+						// kings and castles are marked unmoved by the castling rights below
+						_ => true,
+					},
+					piece_type,
+					owner,
+					promoted: false,
+				});
+			}
+			for _ in 0..empty {
+				row.push(empty_tile(row.len(), y));
+			}
+			tiles.push(row);
+		}
+		let width = tiles[0].len();
+		if !Tiles::is_valid_size(width, tiles.len()) || tiles.iter().any(|row| row.len() != width) {
+			return Err(FenError::InvalidPlacement);
+		}
+
+		let find_king = |owner: Player| {
+			let mut kings = (0..height)
+				.flat_map(|y| (0..width as i8).map(move |x| Vec2(x, y)))
+				.filter(|pos| {
+					matches!(
+						&tiles[pos.1 as usize][pos.0 as usize].piece,
+						Some(Piece { piece_type: PieceType::King, owner: o, .. }) if *o == owner
+					)
+				});
+			match (kings.next(), kings.next()) {
+				(Some(king), None) => Ok(king),
+				_ => Err(FenError::KingCount),
+			}
+		};
+		let kings = [find_king(Player::White)?, find_king(Player::Black)?];
+
+		if castling != "-" {
+			for c in castling.chars() {
+				let owner = match c {
+					'K' | 'Q' => Player::White,
+					'k' | 'q' => Player::Black,
+					_ => return Err(FenError::InvalidCastling),
+				};
+				let king = kings[match owner {
+					Player::White => 0,
+					Player::Black => 1,
+				}];
+				let file = if c.eq_ignore_ascii_case(&'Q') {
+					0
+				} else {
+					width - 1
+				};
+				match &mut tiles[king.1 as usize][file].piece {
+					Some(
+						castle @ Piece {
+							piece_type: PieceType::Castle,
+							..
+						},
+					) if castle.owner == owner => {
+						castle.has_moved = false;
+					}
+					_ => return Err(FenError::InvalidCastling),
+				}
+				if let Some(king) = &mut tiles[king.1 as usize][king.0 as usize].piece {
+					king.has_moved = false;
+				}
+			}
+		}
+
+		if en_passant != "-" {
+			// the pawn that just moved belongs to the player who isn't on turn
+			let target =
+				Vec2::from_square_name(en_passant, height).ok_or(FenError::InvalidEnPassant)?;
+			let pos = target + Piece::pawn_direction(!turn);
+			if !(0..width as i8).contains(&pos.0) || !(0..height).contains(&pos.1) {
+				return Err(FenError::InvalidEnPassant);
+			}
+			match &mut tiles[pos.1 as usize][pos.0 as usize].piece {
+				Some(Piece {
+					piece_type: PieceType::Pawn {
+						turns_since_double_advance,
+					},
+					owner,
+					..
+				}) if *owner == !turn => *turns_since_double_advance = Some(1),
+				_ => return Err(FenError::InvalidEnPassant),
+			}
+		}
+
+		let variant = match duck {
+			Some(_) => Variant::DuckChess,
+			None => Variant::Standard,
+		};
+		let mut board = Board::from_tiles(Tiles::from(tiles), turn, variant, duck);
+		board.halfmove_clock = halfmove_clock;
+		board.fullmove_number = fullmove_number;
+		Ok(board)
+	}
+}
@@ -0,0 +1,48 @@
+use crate::{Bitboard, Board, Player, TurnPhase, Vec2};
+
+// fog of war: players only see their own pieces and the squares those pieces can move to.
+// the views are made on the server, so hidden pieces never reach the client
+impl Board {
+	pub fn visible_squares(&self, player: Player) -> Bitboard {
+		let mut board = self.clone();
+		board.turn = player;
+		board.phase = TurnPhase::Piece;
+		board.generate_moves(false);
+		board
+			.moves
+			.iter()
+			.flatten()
+			// drops dont show anything
+			.filter(|m| m.from.is_inside_board())
+			.fold(self.board.pieces(player), |visible, m| {
+				visible | Bitboard::square(m.to)
+			})
+	}
+	// the board as `player` sees it. everything that could give away a hidden piece is left out,
+	// which includes the other player's moves, the takeback and repetition history and the halfmove
+	// clock, which would tell when a hidden pawn moved or something was captured
+	pub fn fog_view(&self, player: Player) -> Board {
+		let visible = self.visible_squares(player);
+		let mut view = self.clone();
+		for pos in (self.board.occupied() & !visible).squares() {
+			view.set_piece(pos, None);
+		}
+		let king = &mut view.kings[match player {
+			Player::White => 1,
+			Player::Black => 0,
+		}];
+		if !visible.contains(*king) {
+			*king = Vec2::OFF_BOARD;
+		}
+		if view.turn != player {
+			view.move_pieces.clear();
+			view.moves.clear();
+		}
+		view.history.retain(|played| played.player == player);
+		view.takebacks.clear();
+		view.repetitions.clear();
+		view.hash = 0;
+		view.halfmove_clock = 0;
+		view
+	}
+}
@@ -1,11 +1,32 @@
+mod bitboard;
 mod board;
 mod clock;
+mod fen;
+mod fog;
+mod map;
+mod movement;
+mod notation;
+mod outcome;
+mod perft;
+mod pgn;
 mod piece;
 mod play;
+mod ruleset;
+mod variant;
 mod vec2;
+mod zobrist;
 
+pub use bitboard::*;
 pub use board::*;
 pub use clock::*;
+pub use fen::*;
+pub use map::*;
+pub use movement::*;
+pub use notation::*;
+pub use outcome::*;
+pub use pgn::*;
 pub use piece::*;
 pub use play::*;
+pub use ruleset::*;
+pub use variant::*;
 pub use vec2::*;
@@ -0,0 +1,228 @@
+use std::{
+	fmt::{self, Display},
+	str::FromStr,
+};
+
+use rocket::serde::{Deserialize, Serialize};
+
+use crate::{Floor, KING_OFFSETS, MAX_BOARD_WIDTH, Tiles, Vec2};
+
+// the terrain of a board, which stays the same for the whole game.
+// written like a fen placement with one character per square and the last rank first:
+// '.' is a plain square, '#' a wall, 'o' a hole and '~' ice.
+// the size of the map is the size of the board
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", try_from = "String", into = "String")]
+pub struct BoardMap {
+	floors: Vec<Vec<Floor>>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum MapError {
+	InvalidRows,
+	InvalidFloor(char),
+	// the two back ranks of each player are where the setups go
+	SetupRankNotClear,
+	// the kings need a path to each other
+	Unreachable,
+}
+
+impl Display for MapError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			MapError::InvalidRows => {
+				f.write_str("map ranks must all have the same supported width")
+			}
+			MapError::InvalidFloor(c) => write!(f, "invalid floor '{}'", c),
+			MapError::SetupRankNotClear => f.write_str("setup ranks must be plain squares"),
+			MapError::Unreachable => f.write_str("the two sides must be connected"),
+		}
+	}
+}
+
+impl std::error::Error for MapError {}
+
+fn plain_floor(pos: Vec2) -> Floor {
+	if (pos.0 + pos.1) % 2 == 0 {
+		Floor::Light
+	} else {
+		Floor::Dark
+	}
+}
+
+impl BoardMap {
+	// built in maps by name. every one of them is point symmetric, so both sides get the same board
+	pub const BUILT_IN: [(&'static str, &'static str); 7] = [
+		("open", "8/8/8/8/8/8/8/8"),
+		("pillars", "8/8/8/2#2#2/2#2#2/8/8/8"),
+		("pits", "8/8/8/2o5/5o2/8/8/8"),
+		("lake", "8/8/8/2~~~~2/2~~~~2/8/8/8"),
+		("gates", "8/8/8/##2##2/2##2##/8/8/8"),
+		("capablanca", "10/10/10/10/10/10/10/10"),
+		("minichess", "6/6/6/6/6/6"),
+	];
+
+	pub fn built_in(name: &str) -> Option<BoardMap> {
+		Self::BUILT_IN
+			.iter()
+			.find(|(n, _)| *n == name)
+			.map(|(_, map)| map.parse().expect("built in maps are valid"))
+	}
+	pub fn width(&self) -> i8 {
+		self.floors[0].len() as i8
+	}
+	pub fn height(&self) -> i8 {
+		self.floors.len() as i8
+	}
+	pub fn floor(&self, pos: Vec2) -> Floor {
+		self.floors[pos.1 as usize][pos.0 as usize]
+	}
+	fn is_setup_rank(&self, y: i8) -> bool {
+		y < 2 || y >= self.height() - 2
+	}
+	// the setup ranks have to be plain, and a king has to be able to walk from one side to the other
+	pub fn validate(&self) -> Result<(), MapError> {
+		let (width, height) = (self.width(), self.height());
+		let squares = (0..height).flat_map(|y| (0..width).map(move |x| Vec2(x, y)));
+		let passable = |pos: Vec2| !matches!(self.floor(pos), Floor::Wall | Floor::Hole);
+		if squares.clone().any(|pos| {
+			self.is_setup_rank(pos.1) && !matches!(self.floor(pos), Floor::Light | Floor::Dark)
+		}) {
+			return Err(MapError::SetupRankNotClear);
+		}
+		let mut reached = vec![vec![false; width as usize]; height as usize];
+		let mut stack = squares
+			.filter(|pos| pos.1 == height - 1)
+			.collect::<Vec<_>>();
+		while let Some(pos) = stack.pop() {
+			if reached[pos.1 as usize][pos.0 as usize] {
+				continue;
+			}
+			reached[pos.1 as usize][pos.0 as usize] = true;
+			if pos.1 == 0 {
+				return Ok(());
+			}
+			stack.extend(
+				KING_OFFSETS
+					.iter()
+					.map(|offset| pos + *offset)
+					.filter(|to| {
+						(0..width).contains(&to.0) && (0..height).contains(&to.1) && passable(*to)
+					}),
+			);
+		}
+		Err(MapError::Unreachable)
+	}
+}
+
+impl Default for BoardMap {
+	fn default() -> Self {
+		BoardMap::plain(8, 8)
+	}
+}
+
+impl BoardMap {
+	fn plain(width: usize, height: usize) -> Self {
+		BoardMap {
+			floors: (0..height)
+				.map(|y| {
+					(0..width)
+						.map(|x| plain_floor(Vec2(x as i8, y as i8)))
+						.collect()
+				})
+				.collect(),
+		}
+	}
+}
+
+impl FromStr for BoardMap {
+	type Err = MapError;
+
+	// numbers are runs of plain squares, like in fen
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut floors = Vec::new();
+		for (y, rank) in s.trim().split('/').enumerate() {
+			let mut row = Vec::new();
+			let mut plain = 0;
+			for c in rank.chars() {
+				if let Some(digit) = c.to_digit(10) {
+					plain = plain * 10 + digit as usize;
+					if plain > MAX_BOARD_WIDTH as usize {
+						return Err(MapError::InvalidRows);
+					}
+					continue;
+				}
+				for _ in 0..std::mem::take(&mut plain) {
+					row.push(plain_floor(Vec2(row.len() as i8, y as i8)));
+				}
+				row.push(match c {
+					'.' => plain_floor(Vec2(row.len() as i8, y as i8)),
+					'#' => Floor::Wall,
+					'o' => Floor::Hole,
+					'~' => Floor::Ice,
+					_ => return Err(MapError::InvalidFloor(c)),
+				});
+			}
+			for _ in 0..plain {
+				row.push(plain_floor(Vec2(row.len() as i8, y as i8)));
+			}
+			floors.push(row);
+		}
+		let width = floors[0].len();
+		// both sides need their two setup ranks with something in between
+		if floors.iter().any(|row| row.len() != width)
+			|| floors.len() < 5
+			|| !Tiles::is_valid_size(width, floors.len())
+		{
+			return Err(MapError::InvalidRows);
+		}
+		let map = BoardMap { floors };
+		map.validate()?;
+		Ok(map)
+	}
+}
+
+impl Display for BoardMap {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for (y, row) in self.floors.iter().enumerate() {
+			if y > 0 {
+				f.write_str("/")?;
+			}
+			let mut plain = 0;
+			for floor in row {
+				let c = match floor {
+					Floor::Light | Floor::Dark => {
+						plain += 1;
+						continue;
+					}
+					Floor::Wall => '#',
+					Floor::Hole => 'o',
+					Floor::Ice => '~',
+				};
+				if plain > 0 {
+					write!(f, "{}", plain)?;
+					plain = 0;
+				}
+				write!(f, "{}", c)?;
+			}
+			if plain > 0 {
+				write!(f, "{}", plain)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+impl TryFrom<String> for BoardMap {
+	type Error = MapError;
+
+	fn try_from(s: String) -> Result<Self, Self::Error> {
+		s.parse()
+	}
+}
+
+impl From<BoardMap> for String {
+	fn from(map: BoardMap) -> Self {
+		map.to_string()
+	}
+}
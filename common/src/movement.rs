@@ -0,0 +1,353 @@
+use std::{
+	fmt::{self, Display},
+	path::Path,
+	str::FromStr,
+	sync::{LazyLock, OnceLock},
+};
+
+use rocket::serde::{Deserialize, Serialize, json::serde_json};
+
+use crate::{Bitboard, Board, MoveType, PIECE_KINDS, PieceType, Player, Vec2};
+
+// how a piece moves, written in betza notation.
+// atoms are W F D N A H C Z G for the (1,0) (1,1) (2,0) (2,1) (2,2) (3,0) (3,1) (3,2) and (3,3) leapers,
+// plus K = WF, R = WW, B = FF and Q = WWFF.
+// a doubled atom rides in that direction until something is in the way, and a number after it limits how far.
+// lowercase modifiers go in front of an atom: m only moves, c only captures,
+// f and b keep the forward or backward directions and g hops over the first piece in the way.
+// for example the knight is "N", the archbishop "BN" and a pawn without its double step "mfWcfF"
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", try_from = "String", into = "String")]
+pub struct Movement {
+	betza: String,
+	parts: Vec<MovementPart>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct MovementPart {
+	// from white's side, black's are mirrored
+	offsets: Vec<Vec2>,
+	range: i8,
+	can_move: bool,
+	can_capture: bool,
+	hop: bool,
+	// leaps over the squares in between instead of sliding
+	jumps: bool,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum MovementError {
+	InvalidCharacter(char),
+	// modifiers or a range without an atom
+	MissingAtom,
+	InvalidRange,
+}
+
+impl Display for MovementError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			MovementError::InvalidCharacter(c) => write!(f, "invalid character '{}'", c),
+			MovementError::MissingAtom => f.write_str("modifiers and ranges need an atom"),
+			MovementError::InvalidRange => f.write_str("invalid range"),
+		}
+	}
+}
+
+impl std::error::Error for MovementError {}
+
+fn leaper(letter: char) -> Option<Vec2> {
+	Some(match letter {
+		'W' => Vec2(1, 0),
+		'F' => Vec2(1, 1),
+		'D' => Vec2(2, 0),
+		'N' => Vec2(2, 1),
+		'A' => Vec2(2, 2),
+		'H' => Vec2(3, 0),
+		'C' => Vec2(3, 1),
+		'Z' => Vec2(3, 2),
+		'G' => Vec2(3, 3),
+		_ => return None,
+	})
+}
+
+// every rotation and reflection of a leap
+fn symmetric(leap: Vec2) -> Vec<Vec2> {
+	let mut offsets = Vec::new();
+	for Vec2(x, y) in [leap, Vec2(leap.1, leap.0)] {
+		for offset in [Vec2(x, y), Vec2(x, -y), Vec2(-x, y), Vec2(-x, -y)] {
+			if !offsets.contains(&offset) {
+				offsets.push(offset);
+			}
+		}
+	}
+	offsets
+}
+
+impl MovementPart {
+	// riders and hoppers travel, so like sliders they cant stop on ice
+	fn slides(&self) -> bool {
+		self.range > 1 || self.hop
+	}
+	fn move_type(&self) -> MoveType {
+		match self.jumps {
+			true => MoveType::JumpingMove,
+			false => MoveType::SlidingMove,
+		}
+	}
+}
+
+impl Movement {
+	// calls visit with every square a piece of `owner` on `pos` could end up on, whether or not
+	// something is standing there. walls are never visited
+	fn walk(
+		&self,
+		owner: Player,
+		pos: Vec2,
+		board: &Board,
+		mut visit: impl FnMut(&MovementPart, Vec2),
+	) {
+		let blocked = board.blocked();
+		let walls = board.board.walls();
+		for part in &self.parts {
+			for offset in &part.offsets {
+				let dir = match owner {
+					Player::White => *offset,
+					Player::Black => Vec2(offset.0, -offset.1),
+				};
+				let mut to = pos + dir;
+				let mut range = part.range;
+				while board.board.is_inside(to) && range > 0 {
+					if blocked.contains(to) {
+						if part.hop {
+							// lands right behind the hurdle
+							let landing = to + dir;
+							if board.board.is_inside(landing) && !walls.contains(landing) {
+								visit(part, landing);
+							}
+						} else if !walls.contains(to) {
+							visit(part, to);
+						}
+						break;
+					}
+					if !part.hop {
+						visit(part, to);
+					}
+					to += &dir;
+					range -= 1;
+				}
+			}
+		}
+	}
+	// the moves of a piece of `owner` on `pos`, before checking if they are legal
+	pub(crate) fn moves(&self, owner: Player, pos: Vec2, board: &Board) -> Vec<(Vec2, MoveType)> {
+		let occupied = board.occupied();
+		let enemies = board.board.pieces(!owner);
+		let ice = board.board.ice();
+		let mut moves = Vec::new();
+		self.walk(owner, pos, board, |part, to| {
+			let allowed = match occupied.contains(to) {
+				true => part.can_capture && enemies.contains(to),
+				false => part.can_move && !(part.slides() && ice.contains(to)),
+			};
+			if allowed {
+				moves.push((to, part.move_type()));
+			}
+		});
+		moves
+	}
+	// every square a piece of `owner` on `pos` could capture on
+	pub fn attacks(&self, owner: Player, pos: Vec2, board: &Board) -> Bitboard {
+		let mut attacks = Bitboard::EMPTY;
+		self.walk(owner, pos, board, |part, to| {
+			if part.can_capture {
+				attacks.set(to);
+			}
+		});
+		attacks
+	}
+}
+
+impl FromStr for Movement {
+	type Err = MovementError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut parts = Vec::new();
+		let mut chars = s.trim().chars().peekable();
+		let mut modifiers = String::new();
+		while let Some(c) = chars.next() {
+			if "mcfbg".contains(c) {
+				modifiers.push(c);
+				continue;
+			}
+			let (wazir, ferz) = (Vec2(1, 0), Vec2(1, 1));
+			// the compound atoms are always riders or always leapers
+			let (leaps, rides) = match c {
+				'K' => (vec![wazir, ferz], false),
+				'R' => (vec![wazir], true),
+				'B' => (vec![ferz], true),
+				'Q' => (vec![wazir, ferz], true),
+				c if c.is_ascii_digit() => return Err(MovementError::MissingAtom),
+				c => (
+					vec![leaper(c).ok_or(MovementError::InvalidCharacter(c))?],
+					false,
+				),
+			};
+			let mut range = if rides { i8::MAX } else { 1 };
+			if chars.next_if_eq(&c).is_some() {
+				range = i8::MAX;
+			}
+			let mut digits = String::new();
+			while let Some(digit) = chars.next_if(|c| c.is_ascii_digit()) {
+				digits.push(digit);
+			}
+			if !digits.is_empty() {
+				range = match digits.parse::<i8>() {
+					Ok(0) => i8::MAX,
+					Ok(range) => range,
+					Err(_) => return Err(MovementError::InvalidRange),
+				};
+			}
+			let moves_only = modifiers.contains('m');
+			let captures_only = modifiers.contains('c');
+			let (forward, backward) = (modifiers.contains('f'), modifiers.contains('b'));
+			for leap in leaps {
+				let offsets = symmetric(leap)
+					.into_iter()
+					// white moves towards y = 0
+					.filter(|offset| {
+						(!forward && !backward)
+							|| (forward && offset.1 < 0)
+							|| (backward && offset.1 > 0)
+					})
+					.collect();
+				parts.push(MovementPart {
+					offsets,
+					range,
+					can_move: moves_only || !captures_only,
+					can_capture: captures_only || !moves_only,
+					hop: modifiers.contains('g'),
+					jumps: modifiers.contains('g') || leap.0.abs().max(leap.1.abs()) > 1,
+				});
+			}
+			modifiers.clear();
+		}
+		if !modifiers.is_empty() {
+			return Err(MovementError::MissingAtom);
+		}
+		Ok(Movement {
+			betza: s.trim().to_string(),
+			parts,
+		})
+	}
+}
+
+impl Display for Movement {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.betza)
+	}
+}
+
+impl TryFrom<String> for Movement {
+	type Error = MovementError;
+
+	fn try_from(s: String) -> Result<Self, Self::Error> {
+		s.parse()
+	}
+}
+
+impl From<Movement> for String {
+	fn from(movement: Movement) -> Self {
+		movement.to_string()
+	}
+}
+
+// the built in pieces in PieceKind order. pawns, ducks and castling are handled in Piece::generate_moves
+static BUILT_IN: LazyLock<[Movement; PIECE_KINDS]> = LazyLock::new(|| {
+	[
+		"K", "Q", "R", "B", "N", "", "", "BN", "RN", "QN", "C", "gQ", "",
+	]
+	.map(|betza| betza.parse().expect("built in movements are valid"))
+});
+
+impl PieceType {
+	pub fn movement(&self) -> &'static Movement {
+		match self {
+			PieceType::Custom { name } => CustomPiece::find(name)
+				.map_or(&BUILT_IN[self.kind() as usize], |piece| &piece.movement),
+			_ => &BUILT_IN[self.kind() as usize],
+		}
+	}
+}
+
+// a piece defined by the server operator, see load_custom_pieces
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct CustomPiece {
+	pub name: String,
+	// used in fen and notation, has to be an uppercase letter no other piece uses
+	pub letter: char,
+	pub movement: Movement,
+	// what it costs in a board setup, see SetupPieceType::setup_value
+	pub setup_value: i32,
+}
+
+#[derive(Debug)]
+pub enum CustomPieceError {
+	Io(std::io::Error),
+	Json(serde_json::Error),
+	DuplicateName(String),
+	InvalidLetter(char),
+	AlreadyLoaded,
+}
+
+impl Display for CustomPieceError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			CustomPieceError::Io(e) => write!(f, "couldnt read custom pieces: {}", e),
+			CustomPieceError::Json(e) => write!(f, "invalid custom pieces: {}", e),
+			CustomPieceError::DuplicateName(name) => write!(f, "duplicate piece name '{}'", name),
+			CustomPieceError::InvalidLetter(c) => write!(f, "letter '{}' is invalid or taken", c),
+			CustomPieceError::AlreadyLoaded => f.write_str("custom pieces were already loaded"),
+		}
+	}
+}
+
+impl std::error::Error for CustomPieceError {}
+
+static CUSTOM_PIECES: OnceLock<Vec<CustomPiece>> = OnceLock::new();
+
+impl CustomPiece {
+	// empty until custom pieces are registered
+	pub fn all() -> &'static [CustomPiece] {
+		CUSTOM_PIECES.get().map_or(&[], |pieces| pieces.as_slice())
+	}
+	pub fn find(name: &str) -> Option<&'static CustomPiece> {
+		Self::all().iter().find(|piece| piece.name == name)
+	}
+	pub(crate) fn index(name: &str) -> Option<usize> {
+		Self::all().iter().position(|piece| piece.name == name)
+	}
+	// can only be done once, before any games are played
+	pub fn register(pieces: Vec<CustomPiece>) -> Result<(), CustomPieceError> {
+		for (i, piece) in pieces.iter().enumerate() {
+			if pieces[..i].iter().any(|other| other.name == piece.name) {
+				return Err(CustomPieceError::DuplicateName(piece.name.clone()));
+			}
+			if !piece.letter.is_ascii_uppercase()
+				|| PieceType::from_letter(piece.letter).is_some()
+				|| pieces[..i].iter().any(|other| other.letter == piece.letter)
+			{
+				return Err(CustomPieceError::InvalidLetter(piece.letter));
+			}
+		}
+		CUSTOM_PIECES
+			.set(pieces)
+			.map_err(|_| CustomPieceError::AlreadyLoaded)
+	}
+	// a json array of custom pieces, like
+	// [{"name": "nightrider", "letter": "S", "movement": "NN", "setupValue": 500}]
+	pub fn load(path: impl AsRef<Path>) -> Result<(), CustomPieceError> {
+		let json = std::fs::read_to_string(path).map_err(CustomPieceError::Io)?;
+		Self::register(serde_json::from_str(&json).map_err(CustomPieceError::Json)?)
+	}
+}
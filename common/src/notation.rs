@@ -0,0 +1,106 @@
+use rocket::serde::{Deserialize, Serialize};
+
+use crate::{Board, GameOutcome, Move, MoveType, PieceType, Player, TurnPhase, WinReason};
+
+// a move that was played in the game, kept in Board::history
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct PlayedMove {
+	pub player: Player,
+	#[serde(rename = "move")]
+	pub mov: Move,
+	// SAN-like notation, like "Nbd2", "exd6", "e8=Q+", "O-O" or "Dc4"
+	pub notation: String,
+}
+
+impl Board {
+	// notation for a legal move of the player on turn, without the check suffix.
+	// needs to be called before the move is made, since it looks at the other legal moves
+	pub fn notation(&self, mov: &Move) -> String {
+		if self.phase == TurnPhase::Duck {
+			// the neutral duck, written after the piece move like "e4@e5"
+			return format!("@{}", mov.to.square_name(self.board.height()));
+		}
+		if let MoveType::Drop { piece } = &mov.move_type {
+			// like "N@f3" or "P@e4"
+			return format!(
+				"{}@{}",
+				piece.letter(),
+				mov.to.square_name(self.board.height())
+			);
+		}
+		if let MoveType::Castle { from, .. } = mov.move_type {
+			// named after the side the castle came from
+			return if from.0 == 0 { "O-O-O" } else { "O-O" }.to_string();
+		}
+		let Some(piece) = &self.get_tile(mov.from).piece else {
+			return String::new();
+		};
+		let capture = matches!(mov.move_type, MoveType::EnPassant)
+			|| self.board.pieces(!piece.owner).contains(mov.to);
+		let mut notation = String::new();
+		match piece.piece_type {
+			PieceType::Pawn { .. } => {
+				if capture {
+					notation.push_str(&mov.from.square_name(self.board.height())[..1]);
+				}
+			}
+			_ => {
+				notation.push(piece.piece_type.letter());
+				notation.push_str(&self.disambiguation(mov));
+			}
+		}
+		if capture {
+			notation.push('x');
+		}
+		notation.push_str(&mov.to.square_name(self.board.height()));
+		if let MoveType::Promotion { into } = &mov.move_type {
+			notation.push('=');
+			notation.push(into.letter());
+		}
+		notation
+	}
+	// "+" if the player on turn is in check, "#" if the game was won on the board
+	pub(crate) fn notation_suffix(&self, outcome: &Option<GameOutcome>) -> &'static str {
+		match outcome {
+			Some(GameOutcome::Win {
+				reason: WinReason::Checkmate | WinReason::KingCapture,
+				..
+			}) => "#",
+			_ if self.rules().has_check() && self.is_in_check(self.turn) => "+",
+			_ => "",
+		}
+	}
+	// the file, rank or square of the moving piece, when another piece of the same kind could move there
+	fn disambiguation(&self, mov: &Move) -> String {
+		let kind_at = |pos| {
+			self.get_tile(pos)
+				.piece
+				.as_ref()
+				.map(|p| p.piece_type.kind())
+		};
+		let others = self
+			.move_pieces
+			.iter()
+			.zip(&self.moves)
+			.filter(|(from, moves)| {
+				// drops come from off the board
+				from.is_inside_board()
+					&& **from != mov.from
+					&& kind_at(**from) == kind_at(mov.from)
+					&& moves.iter().any(|m| m.to == mov.to)
+			})
+			.map(|(from, _)| *from)
+			.collect::<Vec<_>>();
+		let square = mov.from.square_name(self.board.height());
+		if others.is_empty() {
+			String::new()
+		} else if others.iter().all(|from| from.0 != mov.from.0) {
+			square[..1].to_string()
+		} else if others.iter().all(|from| from.1 != mov.from.1) {
+			square[1..].to_string()
+		} else {
+			square
+		}
+	}
+}
@@ -0,0 +1,95 @@
+use std::fmt::{self, Display};
+
+use rocket::serde::{Deserialize, Serialize};
+
+use crate::Player;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+#[serde(
+	crate = "rocket::serde",
+	rename_all = "camelCase",
+	rename_all_fields = "camelCase",
+	tag = "type"
+)]
+pub enum GameOutcome {
+	Win { winner: Player, reason: WinReason },
+	Draw { reason: DrawReason },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub enum WinReason {
+	Checkmate,
+	// in variants without check, or when a setup allows capturing a king on the first move
+	KingCapture,
+	// in variants where being stalemated wins
+	Stalemate,
+	// king of the hill
+	KingOfTheHill,
+	// three-check
+	ThreeChecks,
+	Timeout,
+	Resignation,
+	Abandonment,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub enum DrawReason {
+	Stalemate,
+	Repetition,
+	FiftyMoveRule,
+	InsufficientMaterial,
+}
+
+impl GameOutcome {
+	pub fn winner(&self) -> Option<Player> {
+		match self {
+			GameOutcome::Win { winner, .. } => Some(*winner),
+			GameOutcome::Draw { .. } => None,
+		}
+	}
+}
+
+impl Display for GameOutcome {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			GameOutcome::Win { winner, reason } => write!(
+				f,
+				"{} wins by {}",
+				match winner {
+					Player::White => "white",
+					Player::Black => "black",
+				},
+				reason
+			),
+			GameOutcome::Draw { reason } => write!(f, "draw by {}", reason),
+		}
+	}
+}
+
+impl Display for WinReason {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			WinReason::Checkmate => "checkmate",
+			WinReason::KingCapture => "king capture",
+			WinReason::Stalemate => "stalemate",
+			WinReason::KingOfTheHill => "reaching the hill",
+			WinReason::ThreeChecks => "three checks",
+			WinReason::Timeout => "timeout",
+			WinReason::Resignation => "resignation",
+			WinReason::Abandonment => "abandonment",
+		})
+	}
+}
+
+impl Display for DrawReason {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			DrawReason::Stalemate => "stalemate",
+			DrawReason::Repetition => "threefold repetition",
+			DrawReason::FiftyMoveRule => "the fifty-move rule",
+			DrawReason::InsufficientMaterial => "insufficient material",
+		})
+	}
+}
@@ -0,0 +1,56 @@
+use crate::{Board, Move};
+
+// perft counts the leaf nodes of the legal move tree, for checking movegen against known values
+impl Board {
+	pub fn perft(&mut self, depth: u32) -> u64 {
+		if depth == 0 {
+			return 1;
+		}
+		self.perft_divide(depth)
+			.iter()
+			.map(|(_, nodes)| nodes)
+			.sum()
+	}
+	// node counts split up by the first move
+	pub fn perft_divide(&mut self, depth: u32) -> Vec<(Move, u64)> {
+		if depth == 0 {
+			return vec![];
+		}
+		let move_pieces = std::mem::take(&mut self.move_pieces);
+		let moves = std::mem::take(&mut self.moves);
+		let divided = self
+			.legal_moves()
+			.into_iter()
+			.map(|m| {
+				let nodes = self.perft_move(&m, depth - 1);
+				(m, nodes)
+			})
+			.collect();
+		self.move_pieces = move_pieces;
+		self.moves = moves;
+		divided
+	}
+	fn perft_move(&mut self, mov: &Move, depth: u32) -> u64 {
+		let undo = self.make_move(mov);
+		let nodes = if depth == 0 || undo.king_captured {
+			1
+		} else if depth == 1 {
+			self.legal_moves().len() as u64
+		} else {
+			self.legal_moves()
+				.iter()
+				.map(|m| self.perft_move(m, depth - 1))
+				.sum()
+		};
+		self.unmake_move(undo);
+		nodes
+	}
+	fn legal_moves(&mut self) -> Vec<Move> {
+		self.generate_moves(true);
+		self.move_pieces.clear();
+		std::mem::take(&mut self.moves)
+			.into_iter()
+			.flatten()
+			.collect()
+	}
+}
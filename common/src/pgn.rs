@@ -0,0 +1,177 @@
+use std::time::SystemTime;
+
+use rocket::{
+	serde::{Deserialize, Serialize},
+	time::OffsetDateTime,
+};
+
+use crate::{
+	Board, BoardMap, BoardSetup, ChessClock, GAME_LENGTH, GameOutcome, GameStart, PieceType,
+	PlayedMove, Player, Variant,
+};
+
+// everything needed to write out a finished game
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct GameRecord {
+	pub game_start: GameStart,
+	pub history: Vec<PlayedMove>,
+	pub outcome: GameOutcome,
+	// the clock when the game ended, if it was still around
+	pub clock: Option<ChessClock>,
+	// seconds since the unix epoch
+	pub date: u64,
+}
+
+impl GameRecord {
+	pub fn new(
+		game_start: GameStart,
+		board: &Board,
+		outcome: GameOutcome,
+		clock: Option<ChessClock>,
+	) -> Self {
+		GameRecord {
+			game_start,
+			history: board.history.clone(),
+			outcome,
+			clock,
+			date: SystemTime::now()
+				.duration_since(SystemTime::UNIX_EPOCH)
+				.expect("system time before unix epoch")
+				.as_secs(),
+		}
+	}
+	fn result(&self) -> &'static str {
+		match self.outcome.winner() {
+			Some(Player::White) => "1-0",
+			Some(Player::Black) => "0-1",
+			None => "1/2-1/2",
+		}
+	}
+	// PGN with the setups in WhiteSetup and BlackSetup tags, and the starting position in FEN
+	pub fn to_pgn(&self) -> String {
+		let date = OffsetDateTime::from_unix_timestamp(self.date as i64)
+			.map(|date| {
+				format!(
+					"{:04}.{:02}.{:02}",
+					date.year(),
+					date.month() as u8,
+					date.day()
+				)
+			})
+			.unwrap_or("????.??.??".to_string());
+		let mut tags = vec![
+			("Event", "Duck Chess game".to_string()),
+			("Site", "duckchess".to_string()),
+			("Date", date),
+			("Round", "-".to_string()),
+			("White", self.game_start.white.id.clone()),
+			("Black", self.game_start.black.id.clone()),
+			("Result", self.result().to_string()),
+			("GameId", self.game_start.game_id.clone()),
+			("WhiteSetup", setup_tag(&self.game_start.white.setup)),
+			("BlackSetup", setup_tag(&self.game_start.black.setup)),
+			("SetUp", "1".to_string()),
+			("FEN", Board::new(self.game_start.clone()).to_fen()),
+			("TimeControl", (GAME_LENGTH / 2).to_string()),
+		];
+		if self.game_start.variant != Variant::Standard {
+			tags.push(("Variant", self.game_start.variant.as_str().to_string()));
+		}
+		// the fen cant show terrain
+		if self.game_start.map != BoardMap::default() {
+			tags.push(("Map", self.game_start.map.to_string()));
+		}
+		if let Some(clock) = &self.clock {
+			tags.push(("WhiteClock", clock_tag(clock.time_remaining(Player::White))));
+			tags.push(("BlackClock", clock_tag(clock.time_remaining(Player::Black))));
+		}
+		tags.push(("Termination", self.outcome.to_string()));
+
+		let mut pgn = String::new();
+		for (name, value) in tags {
+			pgn.push_str(&format!("[{} \"{}\"]\n", name, escape(&value)));
+		}
+		pgn.push('\n');
+
+		let mut tokens: Vec<String> = Vec::new();
+		let mut fullmove = 1;
+		for (i, played) in self.history.iter().enumerate() {
+			// neutral duck moves are joined onto the piece move before them
+			if played.notation.starts_with('@')
+				&& let Some(last) = tokens.last_mut()
+			{
+				last.push_str(&played.notation);
+				continue;
+			}
+			match played.player {
+				Player::White => tokens.push(format!("{}.", fullmove)),
+				Player::Black if i == 0 => tokens.push(format!("{}...", fullmove)),
+				Player::Black => {}
+			}
+			if played.player == Player::Black {
+				fullmove += 1;
+			}
+			tokens.push(played.notation.clone());
+		}
+		tokens.push(self.result().to_string());
+		// movetext lines are kept under 80 characters
+		let mut line = String::new();
+		for token in tokens {
+			if !line.is_empty() && line.len() + 1 + token.len() > 79 {
+				pgn.push_str(&line);
+				pgn.push('\n');
+				line.clear();
+			}
+			if !line.is_empty() {
+				line.push(' ');
+			}
+			line.push_str(&token);
+		}
+		pgn.push_str(&line);
+		pgn.push('\n');
+		pgn
+	}
+}
+
+// front and back rank from the player's side, written like a fen rank
+fn setup_tag(setup: &BoardSetup) -> String {
+	setup
+		.rows()
+		.iter()
+		.map(|row| {
+			let mut rank = String::new();
+			let mut empty = 0;
+			for piece in row {
+				match piece {
+					Some(piece) => {
+						if empty > 0 {
+							rank.push_str(&empty.to_string());
+							empty = 0;
+						}
+						rank.push(PieceType::from(piece.clone()).letter());
+					}
+					None => empty += 1,
+				}
+			}
+			if empty > 0 {
+				rank.push_str(&empty.to_string());
+			}
+			rank
+		})
+		.collect::<Vec<String>>()
+		.join("/")
+}
+
+fn clock_tag(seconds: u64) -> String {
+	format!(
+		"{}:{:02}:{:02}",
+		seconds / 3600,
+		seconds / 60 % 60,
+		seconds % 60
+	)
+}
+
+fn escape(value: &str) -> String {
+	value.replace('\\', "\\\\").replace('"', "\\\"")
+}
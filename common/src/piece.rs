@@ -2,10 +2,46 @@ use rocket::serde::{Deserialize, Serialize};
 
 use crate::{
 	board::{Board, Move, MoveType, Player},
+	movement::CustomPiece,
 	vec2::Vec2,
 };
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+pub(crate) const ORTHOGONAL_DIRECTIONS: [Vec2; 4] =
+	[Vec2(0, 1), Vec2(0, -1), Vec2(1, 0), Vec2(-1, 0)];
+pub(crate) const DIAGONAL_DIRECTIONS: [Vec2; 4] =
+	[Vec2(-1, -1), Vec2(-1, 1), Vec2(1, -1), Vec2(1, 1)];
+pub(crate) const KING_OFFSETS: [Vec2; 8] = [
+	Vec2(0, 1),
+	Vec2(0, -1),
+	Vec2(1, 0),
+	Vec2(-1, 0),
+	Vec2(-1, -1),
+	Vec2(-1, 1),
+	Vec2(1, -1),
+	Vec2(1, 1),
+];
+pub(crate) const KNIGHT_OFFSETS: [Vec2; 8] = [
+	Vec2(2, 1),
+	Vec2(2, -1),
+	Vec2(-2, 1),
+	Vec2(-2, -1),
+	Vec2(1, 2),
+	Vec2(1, -2),
+	Vec2(-1, 2),
+	Vec2(-1, -2),
+];
+pub(crate) const CAMEL_OFFSETS: [Vec2; 8] = [
+	Vec2(3, 1),
+	Vec2(3, -1),
+	Vec2(-3, 1),
+	Vec2(-3, -1),
+	Vec2(1, 3),
+	Vec2(1, -3),
+	Vec2(-1, 3),
+	Vec2(-1, -3),
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
 #[serde(
 	crate = "rocket::serde",
 	rename_all = "camelCase",
@@ -23,6 +59,112 @@ pub enum PieceType {
 		turns_since_double_advance: Option<i32>,
 	},
 	Duck,
+	// bishop + knight
+	Archbishop,
+	// castle + knight
+	Chancellor,
+	// queen + knight
+	Amazon,
+	// leaps 3 squares one way and 1 the other
+	Camel,
+	// moves along queen lines, but has to hop over a piece and lands right behind it
+	Grasshopper,
+	// registered by the server operator, see CustomPiece
+	Custom {
+		name: String,
+	},
+}
+
+// PieceType without any per-piece state, used to index bitboards
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PieceKind {
+	King,
+	Queen,
+	Castle,
+	Bishop,
+	Knight,
+	Pawn,
+	Duck,
+	Archbishop,
+	Chancellor,
+	Amazon,
+	Camel,
+	Grasshopper,
+	// every custom piece shares one kind
+	Custom,
+}
+
+pub const PIECE_KINDS: usize = 13;
+
+impl PieceType {
+	// uppercase letter used in notation
+	pub fn letter(&self) -> char {
+		match self {
+			PieceType::King => 'K',
+			PieceType::Queen => 'Q',
+			PieceType::Castle => 'R',
+			PieceType::Bishop => 'B',
+			PieceType::Knight => 'N',
+			PieceType::Pawn { .. } => 'P',
+			PieceType::Duck => 'D',
+			PieceType::Archbishop => 'A',
+			PieceType::Chancellor => 'C',
+			PieceType::Amazon => 'M',
+			PieceType::Camel => 'L',
+			PieceType::Grasshopper => 'G',
+			PieceType::Custom { name } => CustomPiece::find(name).map_or('?', |piece| piece.letter),
+		}
+	}
+	pub fn from_letter(letter: char) -> Option<PieceType> {
+		Some(match letter.to_ascii_uppercase() {
+			'K' => PieceType::King,
+			'Q' => PieceType::Queen,
+			'R' => PieceType::Castle,
+			'B' => PieceType::Bishop,
+			'N' => PieceType::Knight,
+			'P' => PieceType::Pawn {
+				turns_since_double_advance: None,
+			},
+			'D' => PieceType::Duck,
+			'A' => PieceType::Archbishop,
+			'C' => PieceType::Chancellor,
+			'M' => PieceType::Amazon,
+			'L' => PieceType::Camel,
+			'G' => PieceType::Grasshopper,
+			letter => PieceType::Custom {
+				name: CustomPiece::all()
+					.iter()
+					.find(|piece| piece.letter == letter)?
+					.name
+					.clone(),
+			},
+		})
+	}
+	pub fn kind(&self) -> PieceKind {
+		match self {
+			PieceType::King => PieceKind::King,
+			PieceType::Queen => PieceKind::Queen,
+			PieceType::Castle => PieceKind::Castle,
+			PieceType::Bishop => PieceKind::Bishop,
+			PieceType::Knight => PieceKind::Knight,
+			PieceType::Pawn { .. } => PieceKind::Pawn,
+			PieceType::Duck => PieceKind::Duck,
+			PieceType::Archbishop => PieceKind::Archbishop,
+			PieceType::Chancellor => PieceKind::Chancellor,
+			PieceType::Amazon => PieceKind::Amazon,
+			PieceType::Camel => PieceKind::Camel,
+			PieceType::Grasshopper => PieceKind::Grasshopper,
+			PieceType::Custom { .. } => PieceKind::Custom,
+		}
+	}
+	// the same piece, ignoring the pawn's en passant counter. custom pieces all have the same kind,
+	// so they're told apart by name
+	pub fn is_same_piece(&self, other: &PieceType) -> bool {
+		match (self, other) {
+			(PieceType::Pawn { .. }, PieceType::Pawn { .. }) => true,
+			_ => self == other,
+		}
+	}
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -31,117 +173,78 @@ pub struct Piece {
 	pub piece_type: PieceType,
 	pub owner: Player,
 	pub has_moved: bool,
+	// a pawn that promoted, it goes back to the reserve as a pawn when captured in crazyhouse
+	#[serde(default)]
+	pub promoted: bool,
 }
 
 impl Piece {
-	fn generate_simple_moves(
-		&self,
-		offsets: &[Vec2],
-		limit: i8,
-		pos: Vec2,
-		move_type: MoveType,
-		board: &Board,
-	) -> Vec<Move> {
-		let mut moves = Vec::new();
-		for dir in offsets {
-			let mut to = pos;
-			to += dir;
-			let mut limit = limit;
-			while to.is_inside_board() && limit > 0 {
-				if let Some(blocking) = &board.get_tile(to).piece {
-					if blocking.owner != self.owner {
-						// capture
-						moves.push(Move {
-							move_type: move_type.clone(),
-							from: pos,
-							to,
-						});
-					}
-					break;
-				}
-				moves.push(Move {
-					move_type: move_type.clone(),
-					from: pos,
-					to,
-				});
-				to += dir;
-				limit -= 1;
-			}
+	// moves described by the piece's Movement
+	fn generate_simple_moves(&self, pos: Vec2, board: &Board) -> Vec<Move> {
+		self.piece_type
+			.movement()
+			.moves(self.owner, pos, board)
+			.into_iter()
+			.map(|(to, move_type)| Move {
+				move_type,
+				from: pos,
+				to,
+			})
+			.collect()
+	}
+	pub fn pawn_direction(owner: Player) -> Vec2 {
+		match owner {
+			Player::White => Vec2(0, -1),
+			Player::Black => Vec2(0, 1),
 		}
-		return moves;
 	}
-	pub fn generate_moves(&self, board: &Board, pos: Vec2, deep: bool) -> Vec<Move> {
+	// deep generation only keeps the moves the ruleset allows, which in chess with check
+	// means making and unmaking each move to see if it leaves our king open to capture
+	pub fn generate_moves(&self, board: &mut Board, pos: Vec2, deep: bool) -> Vec<Move> {
 		if self.owner != board.turn {
 			return vec![];
 		}
 		let moves = match self.piece_type {
-			PieceType::Duck => {
-				let mut moves = Vec::new();
-				for y in 0..board.board.len() {
-					for x in 0..board.board[y].len() {
-						let tile = Vec2(x as i8, y as i8);
-						if tile == pos {
-							continue;
-						}
-						if board.get_tile(tile).piece.is_some() {
-							continue;
-						}
-						moves.push(Move {
-							move_type: MoveType::JumpingMove,
-							from: pos,
-							to: tile,
-						});
-					}
-				}
-				moves
-			}
+			PieceType::Duck => board
+				.placeable()
+				.squares()
+				.map(|tile| Move {
+					move_type: MoveType::JumpingMove,
+					from: pos,
+					to: tile,
+				})
+				.collect(),
 			PieceType::King => {
-				let mut moves = self.generate_simple_moves(
-					&[
-						Vec2(0, 1),
-						Vec2(0, -1),
-						Vec2(1, 0),
-						Vec2(-1, 0),
-						Vec2(-1, -1),
-						Vec2(-1, 1),
-						Vec2(1, -1),
-						Vec2(1, 1),
-					],
-					1,
-					pos,
-					MoveType::SlidingMove,
-					board,
-				);
-				// castling
-				if !self.has_moved && (pos.0 == 3 || pos.0 == 4) {
-					'castle_position: for castle_position in [Vec2(0, pos.1), Vec2(7, pos.1)] {
+				let mut moves = self.generate_simple_moves(pos, board);
+				// castling, from one of the two middle files
+				let width = board.board.width();
+				if !self.has_moved && (pos.0 == width / 2 - 1 || pos.0 == width / 2) {
+					'castle_position: for castle_position in
+						[Vec2(0, pos.1), Vec2(width - 1, pos.1)]
+					{
 						if let Some(Piece {
 							piece_type: PieceType::Castle,
 							owner,
 							has_moved: false,
+							..
 						}) = &board.get_tile(castle_position).piece
 						{
 							if *owner != self.owner {
 								continue;
 							}
 							let direction = Vec2(if castle_position.0 == 0 { -1 } else { 1 }, 0);
+							let blocked = board.blocked();
 							let mut cur = pos + direction;
-							while (cur + direction).is_inside_board() {
-								if board.get_tile(cur).piece.is_some() {
+							while board.board.is_inside(cur + direction) {
+								if blocked.contains(cur) {
 									continue 'castle_position;
 								}
 								cur += &direction;
 							}
 							let new_king_position = pos + direction * 2;
-							// cant move from, through, or onto an attacked tile
 							let mut cur = pos;
 							while cur != new_king_position + direction {
-								let move_ = Move {
-									move_type: MoveType::JumpingMove,
-									from: pos,
-									to: new_king_position,
-								};
-								if move_.would_cause_lose(board) {
+								if !board.rules().can_castle_through(board, self.owner, cur) {
 									continue 'castle_position;
 								}
 								cur += &direction;
@@ -159,63 +262,16 @@ impl Piece {
 				}
 				moves
 			}
-			PieceType::Queen => self.generate_simple_moves(
-				&[
-					Vec2(0, 1),
-					Vec2(0, -1),
-					Vec2(1, 0),
-					Vec2(-1, 0),
-					Vec2(-1, -1),
-					Vec2(-1, 1),
-					Vec2(1, -1),
-					Vec2(1, 1),
-				],
-				i8::MAX,
-				pos,
-				MoveType::SlidingMove,
-				board,
-			),
-			PieceType::Castle => self.generate_simple_moves(
-				&[Vec2(0, 1), Vec2(0, -1), Vec2(1, 0), Vec2(-1, 0)],
-				i8::MAX,
-				pos,
-				MoveType::SlidingMove,
-				board,
-			),
-			PieceType::Bishop => self.generate_simple_moves(
-				&[Vec2(-1, -1), Vec2(-1, 1), Vec2(1, -1), Vec2(1, 1)],
-				i8::MAX,
-				pos,
-				MoveType::SlidingMove,
-				board,
-			),
-			PieceType::Knight => self.generate_simple_moves(
-				&[
-					Vec2(2, 1),
-					Vec2(2, -1),
-					Vec2(-2, 1),
-					Vec2(-2, -1),
-					Vec2(1, 2),
-					Vec2(1, -2),
-					Vec2(-1, 2),
-					Vec2(-1, -2),
-				],
-				1,
-				pos,
-				MoveType::JumpingMove,
-				board,
-			),
 			PieceType::Pawn { .. } => {
 				let limit = if self.has_moved { 1 } else { 2 };
-				let dir = match self.owner {
-					Player::White => Vec2(0, -1),
-					Player::Black => Vec2(0, 1),
-				};
+				let dir = Self::pawn_direction(self.owner);
+				let blocked = board.blocked();
+				let enemies = board.board.pieces(!self.owner);
 				let mut moves = Vec::new();
 				// advance by 1 and 2
 				for i in 1..=limit {
 					let to = pos + dir * i;
-					if !to.is_inside_board() || board.get_tile(to).piece.is_some() {
+					if !board.board.is_inside(to) || blocked.contains(to) {
 						break;
 					}
 					moves.push(Move {
@@ -227,61 +283,60 @@ impl Piece {
 				// capture moves
 				for side in [Vec2(-1, 0), Vec2(1, 0)] {
 					let to = pos + dir + side;
-					if !to.is_inside_board() {
-						continue;
-					}
-					if let Some(piece) = &board.get_tile(to).piece {
-						if piece.owner != self.owner {
-							moves.push(Move {
-								move_type: MoveType::SlidingMove,
-								from: pos,
-								to,
-							});
-						}
+					if enemies.contains(to) {
+						moves.push(Move {
+							move_type: MoveType::SlidingMove,
+							from: pos,
+							to,
+						});
 					}
 				}
 				// en passant captures
 				for side in [Vec2(-1, 0), Vec2(1, 0)] {
 					let to = pos + side + dir;
-					if !to.is_inside_board() {
+					// the neutral duck can sit on the square the pawn skipped
+					if !board.board.is_inside(to)
+						|| !enemies.contains(pos + side)
+						|| board.occupied().contains(to)
+					{
 						continue;
 					}
-					match &board.get_tile(pos + side).piece {
-						Some(Piece {
-							piece_type:
-								PieceType::Pawn {
-									turns_since_double_advance: Some(1),
-								},
-							owner,
-							..
-						}) => {
-							if *owner != self.owner {
-								moves.push(Move {
-									move_type: MoveType::EnPassant,
-									from: pos,
-									to,
-								})
-							}
-						}
-						_ => {}
+					if let Some(Piece {
+						piece_type:
+							PieceType::Pawn {
+								turns_since_double_advance: Some(1),
+							},
+						..
+					}) = &board.get_tile(pos + side).piece
+					{
+						moves.push(Move {
+							move_type: MoveType::EnPassant,
+							from: pos,
+							to,
+						})
 					}
 				}
+				let final_rank = match self.owner {
+					Player::White => 0,
+					Player::Black => board.board.height() - 1,
+				};
+				let mut promotions = vec![
+					PieceType::Queen,
+					PieceType::Knight,
+					PieceType::Bishop,
+					PieceType::Castle,
+				];
+				// like in capablanca chess, wide boards also promote to the compound pieces
+				if board.board.width() >= 10 {
+					promotions.extend([PieceType::Archbishop, PieceType::Chancellor]);
+				}
 				moves
 					.into_iter()
 					.flat_map(|original_move| {
-						let final_rank = match self.owner {
-							Player::White => 0,
-							Player::Black => 7,
-						};
 						if original_move.to.1 == final_rank {
 							// promotion
-							let mut moves = Vec::with_capacity(4);
-							for into in [
-								PieceType::Queen,
-								PieceType::Knight,
-								PieceType::Bishop,
-								PieceType::Castle,
-							] {
+							let mut moves = Vec::with_capacity(promotions.len());
+							for into in promotions.iter().cloned() {
 								moves.push(Move {
 									to: original_move.to,
 									from: original_move.from,
@@ -295,22 +350,33 @@ impl Piece {
 					})
 					.collect()
 			}
+			_ => self.generate_simple_moves(pos, board),
 		};
-		return if deep {
+		if deep {
+			let rules = board.rules();
 			moves
 				.into_iter()
-				.filter(|m| !m.would_cause_lose(board))
+				.filter(|m| rules.is_legal(board, m))
 				.collect()
 		} else {
 			moves
-		};
+		}
 	}
 	pub fn post_turn(&mut self) {
-		match &mut self.piece_type {
-			PieceType::Pawn {
-				turns_since_double_advance: Some(turns_since_double_advance),
-			} => *turns_since_double_advance += 1,
-			_ => {}
+		if let PieceType::Pawn {
+			turns_since_double_advance: Some(turns_since_double_advance),
+		} = &mut self.piece_type
+		{
+			*turns_since_double_advance += 1;
+		}
+	}
+	// reverses post_turn, used when unmaking moves
+	pub fn unpost_turn(&mut self) {
+		if let PieceType::Pawn {
+			turns_since_double_advance: Some(turns_since_double_advance),
+		} = &mut self.piece_type
+		{
+			*turns_since_double_advance -= 1;
 		}
 	}
 }
@@ -330,6 +396,12 @@ pub enum SetupPieceType {
 	Knight,
 	Pawn,
 	Duck,
+	Archbishop,
+	Chancellor,
+	Amazon,
+	Camel,
+	Grasshopper,
+	Custom { name: String },
 }
 
 impl From<SetupPieceType> for PieceType {
@@ -344,6 +416,12 @@ impl From<SetupPieceType> for PieceType {
 				turns_since_double_advance: None,
 			},
 			SetupPieceType::Duck => PieceType::Duck,
+			SetupPieceType::Archbishop => PieceType::Archbishop,
+			SetupPieceType::Chancellor => PieceType::Chancellor,
+			SetupPieceType::Amazon => PieceType::Amazon,
+			SetupPieceType::Camel => PieceType::Camel,
+			SetupPieceType::Grasshopper => PieceType::Grasshopper,
+			SetupPieceType::Custom { name } => PieceType::Custom { name },
 		}
 	}
 }
@@ -358,6 +436,15 @@ impl SetupPieceType {
 			SetupPieceType::Knight => 300,
 			SetupPieceType::Pawn => 100,
 			SetupPieceType::Duck => 300,
+			SetupPieceType::Archbishop => 800,
+			SetupPieceType::Chancellor => 900,
+			SetupPieceType::Amazon => 1300,
+			SetupPieceType::Camel => 250,
+			SetupPieceType::Grasshopper => 200,
+			// unknown pieces make the setup invalid anyway
+			SetupPieceType::Custom { name } => {
+				CustomPiece::find(name).map_or(0, |piece| piece.setup_value)
+			}
 		}
 	}
 }
@@ -1,4 +1,7 @@
-use crate::{Board, BoardSetup, ChessClock, Move, Player, Vec2};
+use crate::{
+	Board, BoardSetup, ChessClock, GameOutcome, Move, PieceType, PlayedMove, Player, TurnError,
+	TurnPhase, Variant, Vec2,
+};
 use rocket::serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Debug)]
@@ -9,11 +12,38 @@ use rocket::serde::{Deserialize, Serialize};
 	tag = "type"
 )]
 pub enum PlayRequest {
-	Turn { piece_idx: usize, move_idx: usize },
-	ChatMessage { message: String },
+	Turn {
+		piece_idx: usize,
+		move_idx: usize,
+	},
+	// the server finds the matching legal move, promotion is only needed for promoting moves
+	Move {
+		from: Vec2,
+		to: Vec2,
+		promotion: Option<PieceType>,
+	},
+	// puts a captured piece from the reserve on an empty square
+	Drop {
+		piece: PieceType,
+		to: Vec2,
+	},
+	ChatMessage {
+		message: String,
+	},
 	ExpandEloRange,
-	BoardSetup { setup: BoardSetup },
+	BoardSetup {
+		setup: BoardSetup,
+		#[serde(default)]
+		variant: Variant,
+		// name of one of the built in maps, the open board if not given
+		#[serde(default)]
+		map: Option<String>,
+	},
 	Surrender,
+	// asks the opponent to undo moves until it's our turn again
+	TakebackRequest,
+	// accepts the opponent's pending takeback request
+	TakebackAccept,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -25,21 +55,31 @@ pub enum PlayRequest {
 )]
 pub enum PlayResponse {
 	InvalidRequest,
+	InvalidTurn {
+		error: TurnError,
+	},
 	GameState {
-		board: Board,
+		board: Box<Board>,
 		clock: ChessClock,
 	},
 	TurnStart {
 		turn: Player,
 		move_pieces: Vec<Vec2>,
 		moves: Vec<Vec<Move>>,
+		phase: TurnPhase,
+		checks: [u8; 2],
+		in_check: bool,
+		checkers: Vec<Vec2>,
+		last_move: Option<PlayedMove>,
 		clock: ChessClock,
 	},
 	Move {
 		moves: Vec<Move>,
+		// notation of the move, like "Nf3"
+		notation: String,
 	},
 	End {
-		winner: String,
+		outcome: GameOutcome,
 	},
 	ChatMessage {
 		message: ChatMessage,
@@ -47,6 +87,13 @@ pub enum PlayResponse {
 	FullChat {
 		chat: Vec<ChatMessage>,
 	},
+	TakebackRequest {
+		player: Player,
+	},
+	// the board after a takeback, followed by a turn start
+	Takeback {
+		board: Box<Board>,
+	},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -62,4 +109,41 @@ pub struct TurnStart {
 	pub turn: Player,
 	pub move_pieces: Vec<Vec2>,
 	pub moves: Vec<Vec<Move>>,
+	#[serde(default)]
+	pub phase: TurnPhase,
+	// checks given by white and black, for three-check
+	#[serde(default)]
+	pub checks: [u8; 2],
+	// whether the player on turn is in check, always false in variants without check
+	#[serde(default)]
+	pub in_check: bool,
+	// the pieces giving check
+	#[serde(default)]
+	pub checkers: Vec<Vec2>,
+	// None before the first move
+	#[serde(default)]
+	pub last_move: Option<PlayedMove>,
+}
+
+impl TurnStart {
+	pub fn new(board: &Board) -> Self {
+		let checkers = if board.rules().has_check() {
+			board
+				.attackers(board.get_king_position(board.turn), !board.turn)
+				.squares()
+				.collect()
+		} else {
+			vec![]
+		};
+		TurnStart {
+			turn: board.turn,
+			move_pieces: board.move_pieces.clone(),
+			moves: board.moves.clone(),
+			phase: board.phase,
+			checks: board.checks,
+			in_check: !checkers.is_empty(),
+			checkers,
+			last_move: board.history.last().cloned(),
+		}
+	}
 }
@@ -0,0 +1,249 @@
+use crate::{
+	Bitboard, Board, DrawReason, GameOutcome, Move, MoveType, MoveUndo, PieceKind, Player, Variant,
+	Vec2, WinReason,
+};
+
+// the rules of a variant. the default methods are the rules of standard duckchess,
+// so a variant only overrides what it changes
+pub trait Ruleset: Sync {
+	// moves can't leave the mover's king attacked, and having no moves while attacked is checkmate.
+	// without check any move is allowed, and the game is won by capturing the king
+	fn has_check(&self) -> bool {
+		true
+	}
+	// a neutral duck has to be moved to an empty square after every piece move
+	fn has_neutral_duck(&self) -> bool {
+		false
+	}
+	fn allows_owned_ducks(&self) -> bool {
+		!self.has_neutral_duck()
+	}
+	// captured pieces go to the capturer's reserve and can be dropped back on the board
+	fn has_drops(&self) -> bool {
+		false
+	}
+	// whether a generated move can be played by the player on turn
+	fn is_legal(&self, board: &mut Board, mov: &Move) -> bool {
+		!self.has_check() || !mov.would_cause_lose(board)
+	}
+	// whether a king of `player` can castle through `pos`, which is checked for every square from
+	// where the king starts to where it lands. with check it can't castle out of, through or into one
+	fn can_castle_through(&self, board: &Board, player: Player, pos: Vec2) -> bool {
+		!self.has_check() || !board.is_square_attacked(pos, !player)
+	}
+	// effects of a piece move, run by Board::make_move after the move and before the turn passes.
+	// changes have to go through Board::replace_piece so they can be unmade
+	fn after_move(&self, _board: &mut Board, _mov: &Move, _undo: &mut MoveUndo) {}
+	// simple moves that happen because of a piece move, applied after it and sent to clients with it.
+	// called before the move is made
+	fn move_effects(&self, _board: &Board, _mov: &Move) -> Vec<Move> {
+		Vec::new()
+	}
+	// extra ways to win, checked before the usual outcomes
+	fn win_condition(&self, _board: &Board) -> Option<GameOutcome> {
+		None
+	}
+	// when neither player can win anymore
+	fn has_insufficient_material(&self, board: &Board) -> bool {
+		board.has_insufficient_material()
+	}
+	// what happens when the player on turn has no moves and isn't in check
+	fn stalemate(&self, _board: &Board) -> GameOutcome {
+		GameOutcome::Draw {
+			reason: DrawReason::Stalemate,
+		}
+	}
+	// checked after every complete turn, expects moves to already be generated for the player on turn
+	fn outcome(&self, board: &Board) -> Option<GameOutcome> {
+		if let Some(outcome) = self.win_condition(board) {
+			return Some(outcome);
+		}
+		if !board.moves.is_empty() {
+			let reason = if board.repetitions.get(&board.hash).copied().unwrap_or(0) >= 3 {
+				DrawReason::Repetition
+			} else if board.halfmove_clock >= 100 {
+				DrawReason::FiftyMoveRule
+			} else if self.has_insufficient_material(board) {
+				DrawReason::InsufficientMaterial
+			} else {
+				return None;
+			};
+			return Some(GameOutcome::Draw { reason });
+		}
+		Some(if self.has_check() && board.is_in_check(board.turn) {
+			GameOutcome::Win {
+				winner: !board.turn,
+				reason: WinReason::Checkmate,
+			}
+		} else {
+			self.stalemate(board)
+		})
+	}
+}
+
+pub struct StandardRules;
+
+impl Ruleset for StandardRules {}
+
+pub struct DuckChessRules;
+
+impl Ruleset for DuckChessRules {
+	fn has_check(&self) -> bool {
+		false
+	}
+	fn has_neutral_duck(&self) -> bool {
+		true
+	}
+	// like in king capture, a lone king can still walk into capture
+	fn has_insufficient_material(&self, _board: &Board) -> bool {
+		false
+	}
+	// being stalemated wins
+	fn stalemate(&self, board: &Board) -> GameOutcome {
+		GameOutcome::Win {
+			winner: board.turn,
+			reason: WinReason::Stalemate,
+		}
+	}
+}
+
+pub struct KingCaptureRules;
+
+impl Ruleset for KingCaptureRules {
+	fn has_check(&self) -> bool {
+		false
+	}
+	// a lone king can still walk into capture
+	fn has_insufficient_material(&self, _board: &Board) -> bool {
+		false
+	}
+}
+
+pub struct KingOfTheHillRules;
+
+impl KingOfTheHillRules {
+	// the middle two files and ranks, or just the middle one when there's an odd number of them
+	pub fn is_on_hill(board: &Board, pos: Vec2) -> bool {
+		let middle = |len: i8, i: i8| i == len / 2 || (len % 2 == 0 && i == len / 2 - 1);
+		board.board.is_inside(pos)
+			&& middle(board.board.width(), pos.0)
+			&& middle(board.board.height(), pos.1)
+	}
+}
+
+impl Ruleset for KingOfTheHillRules {
+	// a lone king can still walk to the hill
+	fn has_insufficient_material(&self, _board: &Board) -> bool {
+		false
+	}
+	fn win_condition(&self, board: &Board) -> Option<GameOutcome> {
+		[Player::White, Player::Black]
+			.into_iter()
+			.find(|player| Self::is_on_hill(board, board.get_king_position(*player)))
+			.map(|winner| GameOutcome::Win {
+				winner,
+				reason: WinReason::KingOfTheHill,
+			})
+	}
+}
+
+pub struct ThreeCheckRules;
+
+impl Ruleset for ThreeCheckRules {
+	// a king and a minor piece can still give three checks
+	fn has_insufficient_material(&self, _board: &Board) -> bool {
+		false
+	}
+	fn after_move(&self, board: &mut Board, _mov: &Move, _undo: &mut MoveUndo) {
+		// the turn hasnt passed yet, so the player on turn is the one giving check
+		if board.about_to_win() {
+			board.add_check(board.turn);
+		}
+	}
+	fn win_condition(&self, board: &Board) -> Option<GameOutcome> {
+		[Player::White, Player::Black]
+			.into_iter()
+			.find(|player| board.get_checks(*player) >= 3)
+			.map(|winner| GameOutcome::Win {
+				winner,
+				reason: WinReason::ThreeChecks,
+			})
+	}
+}
+
+pub struct AtomicRules;
+
+impl Ruleset for AtomicRules {
+	// the king can't be blown up or left attacked, unless the other king is blown up.
+	// kings next to each other are safe from each other, since taking one would blow up both
+	fn is_legal(&self, board: &mut Board, mov: &Move) -> bool {
+		let player = board.turn;
+		let undo = board.make_move(mov);
+		let king = board.get_king_position(player);
+		let other_king = board.get_king_position(!player);
+		let legal = king != Vec2::OFF_BOARD
+			&& (other_king == Vec2::OFF_BOARD
+				|| Bitboard::king_attacks(king).contains(other_king)
+				|| !board.is_in_check(player));
+		board.unmake_move(undo);
+		legal
+	}
+	// a capture blows up the capturing piece and every piece around it except pawns and ducks
+	fn move_effects(&self, board: &Board, mov: &Move) -> Vec<Move> {
+		let capture = matches!(mov.move_type, MoveType::EnPassant)
+			|| board.board.pieces(!board.turn).contains(mov.to);
+		if !capture {
+			return Vec::new();
+		}
+		let immune = board.board.kind(PieceKind::Pawn) | board.board.kind(PieceKind::Duck);
+		let blast = Bitboard::king_attacks(mov.to) & board.board.occupied() & !immune;
+		std::iter::once(mov.to)
+			.chain(blast.squares().filter(|pos| *pos != mov.from))
+			.map(|pos| Move {
+				move_type: MoveType::Removal,
+				from: pos,
+				to: pos,
+			})
+			.collect()
+	}
+}
+
+pub struct CrazyhouseRules;
+
+impl Ruleset for CrazyhouseRules {
+	fn has_drops(&self) -> bool {
+		true
+	}
+	// captured pieces come back
+	fn has_insufficient_material(&self, _board: &Board) -> bool {
+		false
+	}
+}
+
+pub struct FogOfWarRules;
+
+impl Ruleset for FogOfWarRules {
+	// players cant see whether they are in check, so the king has to be captured
+	fn has_check(&self) -> bool {
+		false
+	}
+	// which also means a lone king can walk into capture
+	fn has_insufficient_material(&self, _board: &Board) -> bool {
+		false
+	}
+}
+
+impl Variant {
+	pub fn ruleset(&self) -> &'static dyn Ruleset {
+		match self {
+			Variant::Standard => &StandardRules,
+			Variant::DuckChess => &DuckChessRules,
+			Variant::KingCapture => &KingCaptureRules,
+			Variant::KingOfTheHill => &KingOfTheHillRules,
+			Variant::ThreeCheck => &ThreeCheckRules,
+			Variant::Atomic => &AtomicRules,
+			Variant::Crazyhouse => &CrazyhouseRules,
+			Variant::FogOfWar => &FogOfWarRules,
+		}
+	}
+}
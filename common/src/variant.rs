@@ -0,0 +1,49 @@
+use rocket::serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub enum Variant {
+	// chess with owned ducks that move instead of a piece
+	#[default]
+	Standard,
+	// one neutral duck that has to be moved to an empty square after every move, without check
+	DuckChess,
+	// like standard, but without check. any move is allowed and capturing the king wins
+	KingCapture,
+	// moving the king onto one of the four center squares wins
+	KingOfTheHill,
+	// giving check three times wins
+	ThreeCheck,
+	// captures blow up the pieces around them
+	Atomic,
+	// captured pieces can be dropped back on the board
+	Crazyhouse,
+	// players only see the squares their pieces can reach
+	FogOfWar,
+}
+
+// which part of the turn the player on turn is in
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub enum TurnPhase {
+	#[default]
+	Piece,
+	// duck chess only, the neutral duck is moved after the piece
+	Duck,
+}
+
+impl Variant {
+	// same as the serialized name
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Variant::Standard => "standard",
+			Variant::DuckChess => "duckChess",
+			Variant::KingCapture => "kingCapture",
+			Variant::KingOfTheHill => "kingOfTheHill",
+			Variant::ThreeCheck => "threeCheck",
+			Variant::Atomic => "atomic",
+			Variant::Crazyhouse => "crazyhouse",
+			Variant::FogOfWar => "fogOfWar",
+		}
+	}
+}
@@ -5,9 +5,34 @@ use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
 #[serde(crate = "rocket::serde", rename_all = "camelCase")]
 pub struct Vec2(pub i8, pub i8);
 
+// the largest board that fits in a bitboard
+pub const MAX_BOARD_WIDTH: i8 = 12;
+pub const MAX_BOARD_HEIGHT: i8 = 10;
+
 impl Vec2 {
+	// used as the from square of things that come from outside the board
+	pub const OFF_BOARD: Vec2 = Vec2(-1, -1);
+
+	// inside the largest possible board, use Tiles::is_inside for the board being played on
 	pub fn is_inside_board(&self) -> bool {
-		self.0 >= 0 && self.1 >= 0 && self.0 < 8 && self.1 < 8
+		self.0 >= 0 && self.1 >= 0 && self.0 < MAX_BOARD_WIDTH && self.1 < MAX_BOARD_HEIGHT
+	}
+	// algebraic name of the square, like "e4". y = 0 is the last rank, so the board height is needed
+	pub fn square_name(&self, height: i8) -> String {
+		format!("{}{}", (b'a' + self.0 as u8) as char, height - self.1)
+	}
+	pub fn from_square_name(name: &str, height: i8) -> Option<Vec2> {
+		let mut chars = name.chars();
+		let file = chars.next()?;
+		let rank: i8 = chars.as_str().parse().ok()?;
+		if !(1..=height).contains(&rank) {
+			return None;
+		}
+		let pos = Vec2(
+			(file as i32 - 'a' as i32).try_into().ok()?,
+			height.checked_sub(rank)?,
+		);
+		pos.is_inside_board().then_some(pos)
 	}
 }
 
@@ -0,0 +1,111 @@
+use crate::{CustomPiece, Piece, PieceType, Player, Vec2};
+
+// keys are derived on the fly instead of being stored in tables,
+// so there's no limit on the number of squares or piece kinds
+const PIECE: u64 = 1;
+const UNMOVED: u64 = 2;
+const EN_PASSANT: u64 = 3;
+const TURN: u64 = 4;
+const NEUTRAL_DUCK: u64 = 5;
+const PHASE: u64 = 6;
+const CHECKS: u64 = 7;
+const RESERVE: u64 = 8;
+const PROMOTED: u64 = 9;
+
+fn splitmix64(seed: u64) -> u64 {
+	let mut z = seed.wrapping_add(0x9e3779b97f4a7c15);
+	z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+	z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+	z ^ (z >> 31)
+}
+
+fn key(category: u64, index: u64) -> u64 {
+	splitmix64((category << 56) ^ index)
+}
+
+fn square_index(pos: Vec2) -> u64 {
+	((pos.1 as u8 as u64) << 8) | pos.0 as u8 as u64
+}
+
+fn kind_index(piece_type: &PieceType) -> u64 {
+	match piece_type {
+		PieceType::King => 0,
+		PieceType::Queen => 1,
+		PieceType::Castle => 2,
+		PieceType::Bishop => 3,
+		PieceType::Knight => 4,
+		PieceType::Pawn { .. } => 5,
+		PieceType::Duck => 6,
+		PieceType::Archbishop => 7,
+		PieceType::Chancellor => 8,
+		PieceType::Amazon => 9,
+		PieceType::Camel => 10,
+		PieceType::Grasshopper => 11,
+		// pieces that arent registered all hash the same, they cant move anyway
+		PieceType::Custom { name } => 12 + CustomPiece::index(name).map_or(0, |i| i as u64 + 1),
+	}
+}
+
+pub(crate) fn piece_key(piece: &Piece, pos: Vec2) -> u64 {
+	let owner = match piece.owner {
+		Player::White => 0,
+		Player::Black => 1,
+	};
+	let square = square_index(pos);
+	let mut hash = key(
+		PIECE,
+		(kind_index(&piece.piece_type) << 24) | (owner << 16) | square,
+	);
+	// a pawn that just double advanced can be captured en passant
+	if let PieceType::Pawn {
+		turns_since_double_advance: Some(1),
+	} = piece.piece_type
+	{
+		hash ^= key(EN_PASSANT, square);
+	}
+	if piece.promoted {
+		hash ^= key(PROMOTED, square);
+	}
+	hash
+}
+
+// the castle on `pos` can still castle, see Board::castling_rights
+pub(crate) fn castling_key(pos: Vec2) -> u64 {
+	key(UNMOVED, square_index(pos))
+}
+
+pub(crate) fn turn_key() -> u64 {
+	key(TURN, 0)
+}
+
+pub(crate) fn neutral_duck_key(pos: Vec2) -> u64 {
+	key(NEUTRAL_DUCK, square_index(pos))
+}
+
+pub(crate) fn duck_phase_key() -> u64 {
+	key(PHASE, 0)
+}
+
+// no checks is part of every position, so it has no key
+pub(crate) fn checks_key(player: Player, checks: u8) -> u64 {
+	if checks == 0 {
+		return 0;
+	}
+	let player = match player {
+		Player::White => 0,
+		Player::Black => 1,
+	};
+	key(CHECKS, (player << 8) | checks as u64)
+}
+
+// one key for each piece in the reserve, the nth piece of a type has its own key
+pub(crate) fn reserve_key(player: Player, piece_type: &PieceType, count: usize) -> u64 {
+	let player = match player {
+		Player::White => 0,
+		Player::Black => 1,
+	};
+	key(
+		RESERVE,
+		(player << 32) | (kind_index(piece_type) << 16) | count as u64,
+	)
+}
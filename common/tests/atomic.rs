@@ -0,0 +1,80 @@
+mod common;
+
+use common::{square, variant_fen};
+use duckchess_common::{
+	Board, GameOutcome, MoveSelection, MoveType, Player, Turn, TurnError, Variant, Vec2, WinReason,
+};
+
+fn turn(board: &Board, from: &str, to: &str) -> Turn {
+	Turn {
+		game_id: board.id.clone(),
+		selection: MoveSelection::Coordinates {
+			from: square(from),
+			to: square(to),
+			promotion: None,
+		},
+	}
+}
+
+#[test]
+fn capture_explodes() {
+	let mut board = variant_fen(Variant::Atomic, "4k3/8/3nr3/4p3/8/5N2/8/R3K3 w - - 0 1");
+	let hash = board.hash;
+	let (moves, outcome) = board.evaluate_turn(&turn(&board, "f3", "e5")).unwrap();
+	assert_eq!(outcome, None);
+	let removed = moves
+		.iter()
+		.filter(|m| matches!(m.move_type, MoveType::Removal))
+		.map(|m| {
+			assert_eq!(m.from, m.to);
+			m.to.square_name(8)
+		})
+		.collect::<Vec<_>>();
+	assert_eq!(removed.len(), 3);
+	for name in ["e5", "d6", "e6"] {
+		assert!(removed.iter().any(|r| r == name));
+		assert!(board.get_tile(square(name)).piece.is_none());
+	}
+	assert_eq!(board.to_fen(), "4k3/8/8/8/8/8/8/R3K3 b - - 0 1");
+	assert_eq!(board.hash, board.compute_hash());
+
+	board.takeback().unwrap();
+	assert_eq!(board.hash, hash);
+	assert_eq!(board.to_fen(), "4k3/8/3nr3/4p3/8/5N2/8/R3K3 w - - 0 1");
+}
+
+#[test]
+fn pawns_and_ducks_survive() {
+	let mut board = variant_fen(Variant::Atomic, "k7/8/8/3pnd2/8/8/8/K3R3 w - - 0 1");
+	board.evaluate_turn(&turn(&board, "e1", "e5")).unwrap();
+	assert_eq!(board.to_fen(), "k7/8/8/3p1d2/8/8/8/K7 b - - 0 1");
+}
+
+#[test]
+fn exploding_the_king_wins() {
+	let mut board = variant_fen(Variant::Atomic, "8/3k4/3p4/8/8/8/8/3QK3 w - - 0 1");
+	let (_, outcome) = board.evaluate_turn(&turn(&board, "d1", "d6")).unwrap();
+	assert_eq!(
+		outcome,
+		Some(GameOutcome::Win {
+			winner: Player::White,
+			reason: WinReason::KingCapture,
+		})
+	);
+	assert_eq!(board.get_king_position(Player::Black), Vec2::OFF_BOARD);
+	assert_eq!(board.history[0].notation, "Qxd6#");
+}
+
+#[test]
+fn cant_explode_own_king() {
+	let mut board = variant_fen(Variant::Atomic, "4k3/8/8/8/8/8/3p4/3QK3 w - - 0 1");
+	assert_eq!(
+		board.evaluate_turn(&turn(&board, "d1", "d2")).unwrap_err(),
+		TurnError::IllegalMove
+	);
+	// the king can't capture at all, since it would blow itself up
+	assert_eq!(
+		board.evaluate_turn(&turn(&board, "e1", "d2")).unwrap_err(),
+		TurnError::IllegalMove
+	);
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use common::{square, variant_fen};
+use duckchess_common::{Board, MoveType, Player, Variant};
+
+fn castles(board: &Board) -> Vec<String> {
+	let mut castles = board
+		.moves
+		.iter()
+		.flatten()
+		.filter(|m| matches!(m.move_type, MoveType::Castle { .. }))
+		.map(|m| m.to.square_name(8))
+		.collect::<Vec<_>>();
+	castles.sort();
+	castles
+}
+
+#[test]
+fn attackers() {
+	let board = Board::from_fen("4r1k1/8/8/8/8/5n2/8/4K3 w - - 0 1").unwrap();
+	let attackers = board.attackers(square("e1"), Player::Black);
+	assert_eq!(attackers.count(), 2);
+	assert!(attackers.contains(square("e8")));
+	assert!(attackers.contains(square("f3")));
+	assert!(board.is_square_attacked(square("e1"), Player::Black));
+	assert!(!board.is_square_attacked(square("d1"), Player::Black));
+	// pawns only attack diagonally
+	let board = Board::from_fen("k7/8/8/8/3p4/8/8/7K w - - 0 1").unwrap();
+	assert!(board.is_square_attacked(square("c3"), Player::Black));
+	assert!(!board.is_square_attacked(square("d3"), Player::Black));
+}
+
+#[test]
+fn attack_map() {
+	let board =
+		Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+	let attacks = board.attack_map(Player::White);
+	// the third rank, the defended second rank and everything but the corners on the first
+	assert_eq!(attacks.count(), 8 + 8 + 6);
+	assert!(attacks.contains(square("d3")));
+	assert!(!attacks.contains(square("e4")));
+	assert!(!attacks.contains(square("a1")));
+}
+
+#[test]
+fn castling_through_check() {
+	// f1 is attacked, so the king can't pass it, but the queen side is fine
+	let board = Board::from_fen("5rk1/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+	assert_eq!(castles(&board), ["c1"]);
+	// only the squares the king crosses matter, not b1
+	let board = Board::from_fen("1r4k1/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+	assert_eq!(castles(&board), ["c1", "g1"]);
+	// castling out of check
+	let board = Board::from_fen("4r1k1/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+	assert!(castles(&board).is_empty());
+	// or into it
+	let board = Board::from_fen("6rk/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+	assert_eq!(castles(&board), ["c1"]);
+}
+
+#[test]
+fn castling_squares_follow_the_ruleset() {
+	let board = variant_fen(Variant::Standard, "5rk1/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+	let rules = board.rules();
+	assert!(!rules.can_castle_through(&board, Player::White, square("f1")));
+	assert!(rules.can_castle_through(&board, Player::White, square("d1")));
+	// without check the king may pass attacked squares
+	let board = variant_fen(Variant::KingCapture, "5rk1/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+	assert!(
+		board
+			.rules()
+			.can_castle_through(&board, Player::White, square("f1"))
+	);
+	assert_eq!(castles(&board), ["c1", "g1"]);
+}
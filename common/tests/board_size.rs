@@ -0,0 +1,202 @@
+mod common;
+
+use common::{setup, variant_fen};
+use duckchess_common::{
+	Board, BoardMap, GameOutcome, GameStart, GameStartPlayer, KingOfTheHillRules, MoveSelection,
+	MoveType, PieceType, Player, SetupPieceType, Turn, Variant, Vec2, WinReason,
+};
+
+fn map_game(map: &str, white: (&str, &str), black: (&str, &str)) -> Board {
+	Board::new(GameStart {
+		white: GameStartPlayer {
+			id: "white".to_string(),
+			setup: setup(white.0, white.1),
+		},
+		black: GameStartPlayer {
+			id: "black".to_string(),
+			setup: setup(black.0, black.1),
+		},
+		game_id: "size".to_string(),
+		variant: Variant::Standard,
+		map: BoardMap::built_in(map).unwrap(),
+	})
+}
+
+fn moves_from(board: &Board, from: Vec2) -> &[duckchess_common::Move] {
+	let i = board.move_pieces.iter().position(|p| *p == from).unwrap();
+	&board.moves[i]
+}
+
+#[test]
+fn wide_board() {
+	let board = map_game(
+		"capablanca",
+		("PPPPPPPPPP", "RNBQKBBNNR"),
+		("PPPPPPPPPP", "RNBQKBBNNR"),
+	);
+	assert_eq!((board.board.width(), board.board.height()), (10, 8));
+	assert_eq!(
+		board.to_fen(),
+		"rnnbbkqbnr/pppppppppp/10/10/10/10/PPPPPPPPPP/RNBQKBBNNR w KQkq - 0 1"
+	);
+	assert_eq!(board.get_king_position(Player::White), Vec2(4, 7));
+	assert_eq!(board.get_king_position(Player::Black), Vec2(5, 0));
+	// 10 pawns and 3 knights with 2 moves each
+	assert_eq!(board.moves.iter().flatten().count(), 26);
+
+	let board = Board::from_fen(&board.to_fen()).unwrap();
+	assert_eq!((board.board.width(), board.board.height()), (10, 8));
+}
+
+#[test]
+fn castling_on_a_wide_board() {
+	let mut board = Board::from_fen("4k5/10/10/10/10/10/10/R3K4R w KQ - 0 1").unwrap();
+	let castles = moves_from(&board, Vec2(4, 7))
+		.iter()
+		.filter_map(|m| match m.move_type {
+			MoveType::Castle { from, to } => Some((from, to, m.to)),
+			_ => None,
+		})
+		.collect::<Vec<_>>();
+	assert_eq!(castles.len(), 2);
+	assert!(castles.contains(&(Vec2(9, 7), Vec2(5, 7), Vec2(6, 7))));
+	assert!(castles.contains(&(Vec2(0, 7), Vec2(3, 7), Vec2(2, 7))));
+
+	board
+		.evaluate_turn(&Turn {
+			game_id: board.id.clone(),
+			selection: MoveSelection::Coordinates {
+				from: Vec2(4, 7),
+				to: Vec2(6, 7),
+				promotion: None,
+			},
+		})
+		.unwrap();
+	assert_eq!(board.history[0].notation, "O-O");
+	assert_eq!(board.to_fen(), "4k5/10/10/10/10/10/10/R4RK3 b - - 1 1");
+}
+
+#[test]
+fn small_board() {
+	let board = map_game("minichess", ("PPPPPP", "RNQKNR"), ("PPPPPP", "RNQKNR"));
+	assert_eq!((board.board.width(), board.board.height()), (6, 6));
+	assert_eq!(
+		board.to_fen(),
+		"rnkqnr/pppppp/6/6/PPPPPP/RNQKNR w KQkq - 0 1"
+	);
+	// nothing can be generated past the edge of the board
+	assert!(
+		board
+			.moves
+			.iter()
+			.flatten()
+			.all(|m| board.board.is_inside(m.to))
+	);
+}
+
+#[test]
+fn promotion_on_a_small_board() {
+	let mut board = Board::from_fen("k5/4P1/6/6/6/K5 w - - 0 1").unwrap();
+	assert_eq!(Vec2::from_square_name("e6", 6), Some(Vec2(4, 0)));
+	assert!(
+		moves_from(&board, Vec2(4, 1))
+			.iter()
+			.all(|m| matches!(m.move_type, MoveType::Promotion { .. }))
+	);
+	board
+		.evaluate_turn(&Turn {
+			game_id: board.id.clone(),
+			selection: MoveSelection::Coordinates {
+				from: Vec2(4, 1),
+				to: Vec2(4, 0),
+				promotion: Some(PieceType::Queen),
+			},
+		})
+		.unwrap();
+	assert_eq!(board.history[0].notation, "e6=Q+");
+
+	// black promotes on the first rank, which is y = 5 here
+	let board = Board::from_fen("k5/6/6/6/1p4/5K b - - 0 1").unwrap();
+	assert!(
+		moves_from(&board, Vec2(1, 4))
+			.iter()
+			.all(|m| matches!(m.move_type, MoveType::Promotion { .. }))
+	);
+}
+
+#[test]
+fn setups_have_to_fit_the_map() {
+	let wide = BoardMap::built_in("capablanca").unwrap();
+	let small = BoardMap::built_in("minichess").unwrap();
+	let standard = setup("PPPPPPPP", "RNBQKBNR");
+	assert!(standard.is_valid_for(Variant::Standard, &BoardMap::default()));
+	assert!(!standard.is_valid_for(Variant::Standard, &wide));
+	assert!(!standard.is_valid_for(Variant::Standard, &small));
+	assert!(setup("PPPPPPPPPP", "RNBQKBBNNR").is_valid_for(Variant::Standard, &wide));
+	assert!(setup("PPPPPP", "RNQKNR").is_valid_for(Variant::Standard, &small));
+	// the value limit grows with the width
+	assert!(!setup("QQQQQQ", "QQQKQQ").is_valid_for(Variant::Standard, &small));
+}
+
+#[test]
+fn rotate_keeps_the_size() {
+	let mut rotated = setup("PPPPPP", "RNQKNR");
+	rotated.rotate();
+	assert_eq!(rotated.rows().len(), 2);
+	assert_eq!(rotated.width(), 6);
+	// the back rank comes first and is mirrored
+	assert!(matches!(rotated.rows()[0][2], Some(SetupPieceType::King)));
+	assert!(matches!(rotated.rows()[0][3], Some(SetupPieceType::Queen)));
+	assert!(
+		rotated.rows()[1]
+			.iter()
+			.all(|p| matches!(p, Some(SetupPieceType::Pawn)))
+	);
+}
+
+#[test]
+fn hill_follows_the_board_size() {
+	let board = Board::from_fen("k5/6/6/6/6/5K w - - 0 1").unwrap();
+	let hill = (0..6)
+		.flat_map(|y| (0..6).map(move |x| Vec2(x, y)))
+		.filter(|pos| KingOfTheHillRules::is_on_hill(&board, *pos))
+		.collect::<Vec<_>>();
+	assert_eq!(hill, [Vec2(2, 2), Vec2(3, 2), Vec2(2, 3), Vec2(3, 3)]);
+
+	let mut board = variant_fen(Variant::KingOfTheHill, "k6/7/7/7/7/2K4/7 w - - 0 1");
+	assert!(KingOfTheHillRules::is_on_hill(&board, Vec2(3, 3)));
+	assert!(!KingOfTheHillRules::is_on_hill(&board, Vec2(3, 4)));
+	board
+		.evaluate_turn(&Turn {
+			game_id: board.id.clone(),
+			selection: MoveSelection::Coordinates {
+				from: Vec2(2, 5),
+				to: Vec2(3, 4),
+				promotion: None,
+			},
+		})
+		.unwrap();
+	assert_eq!(board.outcome(), None);
+}
+
+#[test]
+fn hill_win_on_an_odd_board() {
+	let mut board = variant_fen(Variant::KingOfTheHill, "k6/7/7/7/3K3/7/7 w - - 0 1");
+	let (_, outcome) = board
+		.evaluate_turn(&Turn {
+			game_id: board.id.clone(),
+			selection: MoveSelection::Coordinates {
+				from: Vec2(3, 4),
+				to: Vec2(3, 3),
+				promotion: None,
+			},
+		})
+		.unwrap();
+	assert_eq!(
+		outcome,
+		Some(GameOutcome::Win {
+			winner: Player::White,
+			reason: WinReason::KingOfTheHill,
+		})
+	);
+}
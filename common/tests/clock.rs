@@ -0,0 +1,31 @@
+use duckchess_common::{ChessClock, ClockHistory, GAME_LENGTH, Player, Timer};
+
+// the player stops their timer with `left` seconds on it, ending their turn
+fn end_turn(clock: &mut ChessClock, history: &mut ClockHistory, player: Player, left: u64) {
+	*clock.player_timer(player) = Timer::new(left);
+	history.push(clock);
+}
+
+#[test]
+fn takebacks_give_time_back() {
+	let start = GAME_LENGTH / 2;
+	let mut clock = ChessClock::new();
+	let mut history = ClockHistory::new(&clock);
+	end_turn(&mut clock, &mut history, Player::White, start - 40);
+	end_turn(&mut clock, &mut history, Player::Black, start - 30);
+	end_turn(&mut clock, &mut history, Player::White, start - 140);
+	// black is thinking when the last two turns are taken back
+	clock.player_timer(Player::Black).start();
+
+	assert!(history.take_back(2, &mut clock));
+	assert_eq!(clock.time_remaining(Player::White), start - 40);
+	assert_eq!(clock.time_remaining(Player::Black), start);
+	assert!(matches!(
+		clock.player_timer(Player::Black),
+		Timer::Paused { .. }
+	));
+	// only one more turn can be taken back
+	assert!(!history.take_back(2, &mut clock));
+	assert!(history.take_back(1, &mut clock));
+	assert_eq!(clock.time_remaining(Player::White), start);
+}
@@ -0,0 +1,82 @@
+#![allow(dead_code)]
+
+use duckchess_common::{
+	Board, BoardSetup, GameStart, GameStartPlayer, MoveSelection, SetupPieceType, Turn, Variant,
+	Vec2,
+};
+
+// rows are given from the player's side, front rank first
+pub fn setup(front: &str, back: &str) -> BoardSetup {
+	let row = |row: &str| {
+		row.chars()
+			.map(|c| match c {
+				'K' => Some(SetupPieceType::King),
+				'Q' => Some(SetupPieceType::Queen),
+				'R' => Some(SetupPieceType::Castle),
+				'B' => Some(SetupPieceType::Bishop),
+				'N' => Some(SetupPieceType::Knight),
+				'P' => Some(SetupPieceType::Pawn),
+				'D' => Some(SetupPieceType::Duck),
+				'A' => Some(SetupPieceType::Archbishop),
+				'C' => Some(SetupPieceType::Chancellor),
+				'M' => Some(SetupPieceType::Amazon),
+				'L' => Some(SetupPieceType::Camel),
+				'G' => Some(SetupPieceType::Grasshopper),
+				_ => None,
+			})
+			.collect::<Vec<_>>()
+	};
+	BoardSetup::new(vec![row(front), row(back)])
+}
+
+pub fn game(white: BoardSetup, black: BoardSetup) -> Board {
+	variant_game(Variant::Standard, white, black)
+}
+
+pub fn variant_game(variant: Variant, white: BoardSetup, black: BoardSetup) -> Board {
+	Board::new(GameStart {
+		white: GameStartPlayer {
+			id: "white".to_string(),
+			setup: white,
+		},
+		black: GameStartPlayer {
+			id: "black".to_string(),
+			setup: black,
+		},
+		game_id: "perft".to_string(),
+		variant,
+		map: Default::default(),
+	})
+}
+
+// a position in a variant, with the moves for it generated
+pub fn variant_fen(variant: Variant, fen: &str) -> Board {
+	let mut board = Board::from_fen(fen).unwrap();
+	board.variant = variant;
+	board.generate_moves(true);
+	board
+}
+
+pub fn standard() -> Board {
+	game(setup("PPPPPPPP", "RNBQKBNR"), setup("PPPPPPPP", "RNBKQBNR"))
+}
+
+pub fn square(name: &str) -> Vec2 {
+	let bytes = name.as_bytes();
+	Vec2((bytes[0] - b'a') as i8, (b'8' - bytes[1]) as i8)
+}
+
+pub fn play(board: &mut Board, moves: &[(&str, &str)]) {
+	for (from, to) in moves {
+		board
+			.evaluate_turn(&Turn {
+				game_id: board.id.clone(),
+				selection: MoveSelection::Coordinates {
+					from: square(from),
+					to: square(to),
+					promotion: None,
+				},
+			})
+			.unwrap();
+	}
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use common::{play, square, variant_fen};
+use duckchess_common::{
+	Board, MoveSelection, MoveType, PieceType, Player, Turn, TurnError, Variant, Vec2,
+};
+
+fn drop(board: &mut Board, piece: PieceType, to: &str) -> Result<(), TurnError> {
+	board
+		.evaluate_turn(&Turn {
+			game_id: board.id.clone(),
+			selection: MoveSelection::Drop {
+				piece,
+				to: square(to),
+			},
+		})
+		.map(|_| ())
+}
+
+const PAWN: PieceType = PieceType::Pawn {
+	turns_since_double_advance: None,
+};
+
+#[test]
+fn captures_go_to_the_reserve() {
+	let mut board = variant_fen(Variant::Crazyhouse, "4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1");
+	play(&mut board, &[("e4", "d5")]);
+	assert_eq!(board.get_reserve(Player::White), &[PAWN]);
+	assert!(board.get_reserve(Player::Black).is_empty());
+	assert_eq!(board.hash, board.compute_hash());
+
+	play(&mut board, &[("e8", "e7")]);
+	drop(&mut board, PAWN, "d6").unwrap();
+	assert_eq!(board.history[2].notation, "P@d6+");
+	assert!(board.get_reserve(Player::White).is_empty());
+	assert_eq!(board.to_fen(), "8/4k3/3P4/3P4/8/8/8/4K3 b - - 2 2");
+	assert_eq!(board.hash, board.compute_hash());
+
+	play(&mut board, &[("e7", "d6")]);
+	assert_eq!(board.get_reserve(Player::Black), &[PAWN]);
+}
+
+#[test]
+fn promoted_pieces_go_back_as_pawns() {
+	let mut board = variant_fen(Variant::Crazyhouse, "4k3/1P1n4/8/8/8/8/8/4K3 w - - 0 1");
+	board
+		.evaluate_turn(&Turn {
+			game_id: board.id.clone(),
+			selection: MoveSelection::Coordinates {
+				from: square("b7"),
+				to: square("b8"),
+				promotion: Some(PieceType::Queen),
+			},
+		})
+		.unwrap();
+	assert!(
+		board
+			.get_tile(square("b8"))
+			.piece
+			.as_ref()
+			.unwrap()
+			.promoted
+	);
+	assert_eq!(board.hash, board.compute_hash());
+	play(&mut board, &[("d7", "b8")]);
+	assert_eq!(board.get_reserve(Player::Black), &[PAWN]);
+	assert_eq!(board.hash, board.compute_hash());
+	board.takeback().unwrap();
+	assert!(
+		board
+			.get_tile(square("b8"))
+			.piece
+			.as_ref()
+			.unwrap()
+			.promoted
+	);
+	assert!(board.get_reserve(Player::Black).is_empty());
+}
+
+#[test]
+fn pawns_cant_be_dropped_on_the_back_ranks() {
+	let mut board = variant_fen(Variant::Crazyhouse, "4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1");
+	play(&mut board, &[("e4", "d5"), ("e8", "e7")]);
+	let drops = board
+		.move_pieces
+		.iter()
+		.position(|p| *p == Vec2::OFF_BOARD)
+		.map(|i| &board.moves[i])
+		.unwrap();
+	assert!(
+		drops
+			.iter()
+			.all(|m| matches!(m.move_type, MoveType::Drop { .. }) && m.to.1 != 0 && m.to.1 != 7)
+	);
+	assert_eq!(drop(&mut board, PAWN, "a8"), Err(TurnError::IllegalMove));
+	assert_eq!(
+		drop(&mut board, PieceType::Knight, "f5"),
+		Err(TurnError::IllegalMove)
+	);
+}
+
+#[test]
+fn takeback_returns_the_piece() {
+	let mut board = variant_fen(Variant::Crazyhouse, "4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1");
+	play(&mut board, &[("e4", "d5"), ("e8", "e7")]);
+	let hash = board.hash;
+	drop(&mut board, PAWN, "d6").unwrap();
+	board.takeback().unwrap();
+	assert_eq!(board.get_reserve(Player::White), &[PAWN]);
+	assert_eq!(board.hash, hash);
+	board.takeback().unwrap();
+	board.takeback().unwrap();
+	assert!(board.get_reserve(Player::White).is_empty());
+	assert_eq!(board.to_fen(), "4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1");
+}
+
+#[test]
+fn only_in_crazyhouse() {
+	let mut board = Board::from_fen("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1").unwrap();
+	play(&mut board, &[("e4", "d5")]);
+	assert!(board.get_reserve(Player::White).is_empty());
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::{square, variant_fen};
+use duckchess_common::{Board, DrawReason, GameOutcome, MoveSelection, Turn, Variant, Vec2};
+
+fn turn(board: &mut Board, from: Vec2, to: &str) -> Option<GameOutcome> {
+	board
+		.evaluate_turn(&Turn {
+			game_id: board.id.clone(),
+			selection: MoveSelection::Coordinates {
+				from,
+				to: square(to),
+				promotion: None,
+			},
+		})
+		.unwrap()
+		.1
+}
+
+fn draw(reason: DrawReason) -> Option<GameOutcome> {
+	Some(GameOutcome::Draw { reason })
+}
+
+#[test]
+fn lone_minor_piece_draws_only_with_check() {
+	// the king takes the queen, leaving a knight
+	let fen = "4k3/8/8/8/8/8/3q4/4KN2 w - - 0 1";
+	let mut board = variant_fen(Variant::Standard, fen);
+	assert_eq!(
+		turn(&mut board, square("e1"), "d2"),
+		draw(DrawReason::InsufficientMaterial)
+	);
+	// a lone king can walk into capture, and a knight can still give three checks
+	for variant in [Variant::KingCapture, Variant::ThreeCheck, Variant::FogOfWar] {
+		let mut board = variant_fen(variant, fen);
+		assert_eq!(turn(&mut board, square("e1"), "d2"), None, "{:?}", variant);
+	}
+}
+
+#[test]
+fn fifty_move_rule() {
+	let fen = "4k3/8/8/8/8/8/P7/R3K3 w - - 99 60";
+	let mut board = variant_fen(Variant::Standard, fen);
+	assert_eq!(
+		turn(&mut board, square("a1"), "b1"),
+		draw(DrawReason::FiftyMoveRule)
+	);
+	// a pawn move resets the count
+	let mut board = variant_fen(Variant::Standard, fen);
+	assert_eq!(turn(&mut board, square("a2"), "a3"), None);
+	assert_eq!(board.halfmove_clock, 0);
+	// and so does a capture
+	let mut board = variant_fen(Variant::Standard, "4k3/8/8/8/8/8/p7/R3K3 w - - 99 60");
+	assert_eq!(turn(&mut board, square("a1"), "a2"), None);
+	assert_eq!(board.halfmove_clock, 0);
+}
+
+#[test]
+fn lone_kings() {
+	let mut board = variant_fen(Variant::Standard, "4k3/8/8/8/8/8/3q4/4K3 w - - 0 1");
+	assert_eq!(
+		turn(&mut board, square("e1"), "d2"),
+		draw(DrawReason::InsufficientMaterial)
+	);
+	// a duck on its own can't mate either
+	let mut board = variant_fen(Variant::Standard, "4k3/8/8/8/8/8/3q4/4K2D w - - 0 1");
+	assert_eq!(
+		turn(&mut board, square("e1"), "d2"),
+		draw(DrawReason::InsufficientMaterial)
+	);
+	// without check a lone king can still walk into capture
+	let mut board = variant_fen(Variant::FogOfWar, "4k3/8/8/8/8/8/3q4/4K3 w - - 0 1");
+	assert_eq!(turn(&mut board, square("e1"), "d2"), None);
+	let mut board = variant_fen(Variant::DuckChess, "4k2*/8/8/8/8/8/3q4/4K3 w - - 0 1");
+	assert_eq!(turn(&mut board, square("e1"), "d2"), None);
+	assert_eq!(turn(&mut board, square("h8"), "h7"), None);
+	let mut board = variant_fen(Variant::DuckChess, "4k3/8/8/8/8/7*/8/4K3 w - - 0 1");
+	assert_eq!(turn(&mut board, square("e1"), "e2"), None);
+	assert_eq!(turn(&mut board, square("h3"), "h4"), None);
+}
+
+#[test]
+fn minor_piece_and_duck() {
+	// a duck can cover the squares a lone knight can't
+	let mut board = variant_fen(Variant::Standard, "4k3/8/8/8/8/8/3q4/4KN1D w - - 0 1");
+	assert_eq!(turn(&mut board, square("e1"), "d2"), None);
+	// without any duck it's a draw
+	let mut board = variant_fen(Variant::Standard, "4k3/8/8/8/8/8/3q4/4KB2 w - - 0 1");
+	assert_eq!(
+		turn(&mut board, square("e1"), "d2"),
+		draw(DrawReason::InsufficientMaterial)
+	);
+}
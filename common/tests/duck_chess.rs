@@ -0,0 +1,160 @@
+mod common;
+
+use common::{play, setup, square, variant_game};
+use duckchess_common::{
+	Board, BoardMap, DrawReason, GameOutcome, MoveSelection, Player, Turn, TurnPhase, Variant,
+	Vec2, WinReason,
+};
+
+fn duck_chess() -> Board {
+	variant_game(
+		Variant::DuckChess,
+		setup("PPPPPPPP", "RNBQKBNR"),
+		setup("PPPPPPPP", "RNBKQBNR"),
+	)
+}
+
+// the first duck placement, which comes from off the board
+fn place_duck(board: &mut Board, to: &str) {
+	board
+		.evaluate_turn(&Turn {
+			game_id: board.id.clone(),
+			selection: MoveSelection::Coordinates {
+				from: Vec2::OFF_BOARD,
+				to: square(to),
+				promotion: None,
+			},
+		})
+		.unwrap();
+}
+
+#[test]
+fn duck_moves_after_every_piece_move() {
+	let mut board = duck_chess();
+	assert_eq!(board.duck, None);
+	play(&mut board, &[("e2", "e4")]);
+	assert_eq!(board.turn, Player::White);
+	assert_eq!(board.phase, TurnPhase::Duck);
+	// the first placement comes from off the board, onto any empty square
+	assert_eq!(board.move_pieces, [Vec2::OFF_BOARD]);
+	assert_eq!(board.moves[0].len(), 32);
+	place_duck(&mut board, "e5");
+	assert_eq!(board.turn, Player::Black);
+	assert_eq!(board.phase, TurnPhase::Piece);
+	assert_eq!(board.duck, Some(square("e5")));
+	assert_eq!(board.hash, board.compute_hash());
+	// the duck blocks the double advance
+	let e7 = board
+		.move_pieces
+		.iter()
+		.position(|p| *p == square("e7"))
+		.unwrap();
+	assert_eq!(board.moves[e7].len(), 1);
+	play(&mut board, &[("d7", "d5"), ("e5", "e6")]);
+	assert_eq!(board.duck, Some(square("e6")));
+	assert_eq!(board.turn, Player::White);
+	assert_eq!(
+		board
+			.history
+			.iter()
+			.map(|played| played.notation.as_str())
+			.collect::<Vec<_>>(),
+		["e4", "@e5", "d5", "@e6"]
+	);
+}
+
+#[test]
+fn duck_cant_be_captured_or_passed() {
+	let board = Board::from_fen("4k3/8/8/8/8/8/3*4/R3K3 w - - 0 1").unwrap();
+	assert_eq!(board.variant, Variant::DuckChess);
+	let a1 = board
+		.move_pieces
+		.iter()
+		.position(|p| *p == square("a1"))
+		.unwrap();
+	// a2 to a8 and b1 to d1
+	assert_eq!(board.moves[a1].len(), 10);
+	let e1 = board
+		.move_pieces
+		.iter()
+		.position(|p| *p == square("e1"))
+		.unwrap();
+	assert!(!board.moves[e1].iter().any(|m| m.to == square("d2")));
+}
+
+#[test]
+fn no_en_passant_onto_the_duck() {
+	let mut board = Board::from_fen("4k3/3p4/8/4P3/8/7*/8/4K3 b - - 0 1").unwrap();
+	play(&mut board, &[("d7", "d5"), ("h3", "d6")]);
+	let e5 = board
+		.move_pieces
+		.iter()
+		.position(|p| *p == square("e5"))
+		.unwrap();
+	assert!(board.moves[e5].iter().all(|m| m.to != square("d6")));
+	assert_eq!(board.to_fen(), "4k3/8/3*4/3pP3/8/8/8/4K3 w - d6 0 2");
+}
+
+#[test]
+fn fen_round_trip() {
+	let fen = "rnbqkbnr/pppp1ppp/8/4p3/3*P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2";
+	let board = Board::from_fen(fen).unwrap();
+	assert_eq!(board.to_fen(), fen);
+	assert_eq!(board.duck, Some(square("d4")));
+}
+
+#[test]
+fn being_stalemated_wins() {
+	// black's own pawns and the duck box the king in
+	let board = Board::from_fen("K7/8/8/8/8/8/6pp/6*k b - - 0 1").unwrap();
+	assert_eq!(
+		board.outcome(),
+		Some(GameOutcome::Win {
+			winner: Player::Black,
+			reason: WinReason::Stalemate,
+		})
+	);
+	let board = Board::from_fen("k7/8/1Q6/8/8/8/8/7K b - - 0 1").unwrap();
+	assert_eq!(
+		board.outcome(),
+		Some(GameOutcome::Draw {
+			reason: DrawReason::Stalemate,
+		})
+	);
+}
+
+#[test]
+fn owned_ducks_are_not_allowed() {
+	let with_ducks = setup("PPPDDPPP", "RNBQKBNR");
+	let map = BoardMap::default();
+	assert!(with_ducks.is_valid_for(Variant::Standard, &map));
+	assert!(!with_ducks.is_valid_for(Variant::DuckChess, &map));
+	assert!(setup("PPPPPPPP", "RNBQKBNR").is_valid_for(Variant::DuckChess, &map));
+}
+
+#[test]
+fn takeback_in_the_middle_of_a_turn() {
+	let mut board = duck_chess();
+	play(&mut board, &[("e2", "e4")]);
+	place_duck(&mut board, "e5");
+	play(&mut board, &[("d7", "d5")]);
+	let hash = board.hash;
+	play(&mut board, &[("e5", "e6")]);
+	board.takeback().unwrap();
+	assert_eq!(board.hash, hash);
+	assert_eq!(board.phase, TurnPhase::Duck);
+	assert_eq!(board.turn, Player::Black);
+	board.takeback().unwrap();
+	assert_eq!(board.phase, TurnPhase::Piece);
+	assert_eq!(board.turn, Player::Black);
+	assert_eq!(board.hash, board.compute_hash());
+	assert_eq!(board.duck, Some(square("e5")));
+}
+
+#[test]
+fn perft() {
+	let mut board = duck_chess();
+	assert_eq!(board.perft(1), 20);
+	// every white move leaves 32 empty squares for the duck
+	assert_eq!(board.perft(2), 640);
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use common::{play, setup, square};
+use duckchess_common::{
+	Board, BoardMap, Move, MoveType, PieceType, Player, SetupPieceType, Variant,
+};
+
+fn moves_from<'a>(board: &'a Board, from: &str) -> &'a [Move] {
+	// pieces without any moves aren't listed
+	board
+		.move_pieces
+		.iter()
+		.position(|p| *p == square(from))
+		.map_or(&[], |i| &board.moves[i])
+}
+
+fn targets(board: &Board, from: &str) -> Vec<String> {
+	let mut targets = moves_from(board, from)
+		.iter()
+		.map(|m| m.to.square_name(8))
+		.collect::<Vec<_>>();
+	targets.sort();
+	targets
+}
+
+#[test]
+fn compound_pieces() {
+	let board = Board::from_fen("k7/8/8/8/3A4/8/8/7K w - - 0 1").unwrap();
+	// 13 bishop moves and 8 knight moves
+	assert_eq!(moves_from(&board, "d4").len(), 21);
+	let board = Board::from_fen("k7/8/8/8/3C4/8/8/7K w - - 0 1").unwrap();
+	assert_eq!(moves_from(&board, "d4").len(), 14 + 8);
+	let board = Board::from_fen("k7/8/8/8/3M4/8/8/7K w - - 0 1").unwrap();
+	assert_eq!(moves_from(&board, "d4").len(), 27 + 8);
+	assert_eq!(board.to_fen(), "k7/8/8/8/3M4/8/8/7K w - - 0 1");
+}
+
+#[test]
+fn camel() {
+	let board = Board::from_fen("k7/8/8/8/3L4/8/8/7K w - - 0 1").unwrap();
+	assert_eq!(
+		targets(&board, "d4"),
+		["a3", "a5", "c1", "c7", "e1", "e7", "g3", "g5"]
+	);
+	// it jumps like a knight, so nothing in between matters
+	let board = Board::from_fen("k7/8/8/8/PPPL4/8/8/7K w - - 0 1").unwrap();
+	assert!(targets(&board, "d4").contains(&"a3".to_string()));
+}
+
+#[test]
+fn grasshopper_needs_a_hurdle() {
+	let board = Board::from_fen("k7/8/3n4/3p4/8/3G4/8/7K w - - 0 1").unwrap();
+	// it lands right behind the pawn, capturing the knight there
+	assert_eq!(targets(&board, "d3"), ["d6"]);
+	let board = Board::from_fen("k7/8/3N4/3p4/8/3G4/8/7K w - - 0 1").unwrap();
+	assert!(targets(&board, "d3").is_empty());
+	// the edge of the board isn't a hurdle
+	let board = Board::from_fen("k7/8/8/8/8/8/8/G6K w - - 0 1").unwrap();
+	assert!(targets(&board, "a1").is_empty());
+}
+
+#[test]
+fn fairy_pieces_give_check() {
+	let board = Board::from_fen("3k4/3p4/8/8/3G4/8/8/7K b - - 0 1").unwrap();
+	assert!(board.is_in_check(Player::Black));
+	let board = Board::from_fen("3k4/3p4/8/3P4/3G4/8/8/7K b - - 0 1").unwrap();
+	assert!(!board.is_in_check(Player::Black));
+	let board = Board::from_fen("3k4/8/8/4L3/8/8/8/7K b - - 0 1").unwrap();
+	assert!(board.is_in_check(Player::Black));
+	let board = Board::from_fen("3k4/8/4A3/8/8/8/8/7K b - - 0 1").unwrap();
+	assert!(board.is_in_check(Player::Black));
+	let board = Board::from_fen("3k4/8/8/8/8/8/8/3C3K b - - 0 1").unwrap();
+	assert!(board.is_in_check(Player::Black));
+}
+
+#[test]
+fn play_with_fairy_pieces() {
+	let mut board = Board::from_fen("k7/8/8/8/3p4/8/8/3G3K w - - 0 1").unwrap();
+	play(&mut board, &[("d1", "d5")]);
+	assert_eq!(board.history[0].notation, "Gd5");
+	assert_eq!(board.hash, board.compute_hash());
+	board.takeback().unwrap();
+	assert_eq!(board.to_fen(), "k7/8/8/8/3p4/8/8/3G3K w - - 0 1");
+}
+
+#[test]
+fn promotion_options() {
+	let board = Board::from_fen("k7/4P3/8/8/8/8/8/7K w - - 0 1").unwrap();
+	assert_eq!(moves_from(&board, "e7").len(), 4);
+	// wide boards also promote to archbishops and chancellors
+	let board = Board::from_fen("k9/4P5/10/10/10/10/10/9K w - - 0 1").unwrap();
+	let promotions = moves_from(&board, "e7");
+	assert_eq!(promotions.len(), 6);
+	for piece in [PieceType::Archbishop, PieceType::Chancellor] {
+		assert!(
+			promotions
+				.iter()
+				.any(|m| matches!(&m.move_type, MoveType::Promotion { into } if *into == piece))
+		);
+	}
+}
+
+#[test]
+fn setup_values() {
+	let map = BoardMap::default();
+	assert!(setup("PPPPPPPP", "RNBCKANR").is_valid_for(Variant::Standard, &map));
+	assert!(setup("PPPPPPPP", "RLGQKGLR").is_valid_for(Variant::Standard, &map));
+	// an amazon instead of the queen goes over the budget with everything else there
+	assert!(!setup("PPPPPPPP", "RNBMKANR").is_valid_for(Variant::Standard, &map));
+	assert!(SetupPieceType::Amazon.setup_value() > SetupPieceType::Queen.setup_value());
+}
+
+#[test]
+fn insufficient_material() {
+	let board = Board::from_fen("k7/8/8/8/8/8/8/L6K w - - 0 1").unwrap();
+	assert!(board.has_insufficient_material());
+	for piece in ['A', 'C', 'M', 'G'] {
+		let board = Board::from_fen(&format!("k7/8/8/8/8/8/8/{}6K w - - 0 1", piece)).unwrap();
+		assert!(!board.has_insufficient_material());
+	}
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use common::{game, play, setup, standard};
+use duckchess_common::{Board, FenError, PieceType, Player};
+
+const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[test]
+fn standard_position() {
+	let board = standard();
+	assert_eq!(board.to_fen(), START);
+	let parsed = Board::from_fen(START).unwrap();
+	assert_eq!(parsed.hash, board.hash);
+	assert_eq!(parsed.to_fen(), START);
+}
+
+#[test]
+fn en_passant_and_counters() {
+	let mut board = standard();
+	play(&mut board, &[("e2", "e4")]);
+	assert_eq!(
+		board.to_fen(),
+		"rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+	);
+	play(&mut board, &[("g8", "f6"), ("e4", "e5"), ("d7", "d5")]);
+	let fen = "rnbqkb1r/ppp1pppp/5n2/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3";
+	assert_eq!(board.to_fen(), fen);
+	let parsed = Board::from_fen(fen).unwrap();
+	assert_eq!(parsed.hash, board.hash);
+	assert_eq!(parsed.to_fen(), fen);
+	play(&mut board, &[("g1", "f3")]);
+	assert_eq!(
+		board.to_fen(),
+		"rnbqkb1r/ppp1pppp/5n2/3pP3/8/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 3"
+	);
+}
+
+#[test]
+fn castling_rights_follow_has_moved() {
+	let mut board = standard();
+	play(
+		&mut board,
+		&[("e2", "e4"), ("h7", "h5"), ("e1", "e2"), ("h8", "h6")],
+	);
+	assert_eq!(
+		board.to_fen(),
+		"rnbqkbn1/ppppppp1/7r/7p/4P3/8/PPPPKPPP/RNBQ1BNR w q - 2 3"
+	);
+}
+
+#[test]
+fn ducks() {
+	let board = game(setup("PPPDDPPP", "RNBQKBNR"), setup("PPPPPPPP", "RNBKQBNR"));
+	let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPDDPPP/RNBQKBNR w KQkq - 0 1";
+	assert_eq!(board.to_fen(), fen);
+	assert_eq!(Board::from_fen(fen).unwrap().hash, board.hash);
+	let board = Board::from_fen("4k3/8/3d4/8/8/8/8/3DK3 b - - 0 1").unwrap();
+	assert_eq!(board.turn, Player::Black);
+	let duck = board.board.get(common::square("d6")).piece.clone().unwrap();
+	assert_eq!(duck.piece_type, PieceType::Duck);
+	assert_eq!(duck.owner, Player::Black);
+}
+
+#[test]
+fn optional_counters() {
+	let board = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - -").unwrap();
+	assert_eq!(board.halfmove_clock, 0);
+	assert_eq!(board.fullmove_number, 1);
+}
+
+#[test]
+fn invalid() {
+	let error = |fen| Board::from_fen(fen).err();
+	assert_eq!(
+		error("4k3/8/8/8/8/8/8/8/8/8/4K3 w - -"),
+		Some(FenError::InvalidPlacement)
+	);
+	assert_eq!(
+		error("4k3/8/8/8/8/8/8/4K4 w - -"),
+		Some(FenError::InvalidPlacement)
+	);
+	assert_eq!(
+		error("4k3/8/8/8/8/8/8/4X3 w - -"),
+		Some(FenError::InvalidPiece('X'))
+	);
+	assert_eq!(error("4k3/8/8/8/8/8/8/8 w - -"), Some(FenError::KingCount));
+	assert_eq!(
+		error("4k3/8/8/8/8/8/8/4K3 x - -"),
+		Some(FenError::InvalidTurn)
+	);
+	assert_eq!(
+		error("4k3/8/8/8/8/8/8/4K3 w K -"),
+		Some(FenError::InvalidCastling)
+	);
+	assert_eq!(
+		error("4k3/8/8/8/8/8/8/4K3 w - e3"),
+		Some(FenError::InvalidEnPassant)
+	);
+	// ranks that would overflow
+	for square in ["a-128", "a-121", "a0", "a9", "a127"] {
+		assert_eq!(
+			Board::from_fen(&format!("4k3/8/8/8/8/8/8/4K3 w - {}", square)).err(),
+			Some(FenError::InvalidEnPassant)
+		);
+	}
+	assert_eq!(
+		error("4k3/8/8/8/8/8/8/4K3 w -"),
+		Some(FenError::MissingField)
+	);
+	assert_eq!(
+		error("4k3/8/8/8/8/8/8/4K3 w - - x 1"),
+		Some(FenError::InvalidNumber)
+	);
+}
+
+// https://www.chessprogramming.org/Perft_Results position 3
+#[test]
+fn perft_from_fen() {
+	let mut board = Board::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap();
+	for (depth, nodes) in [14, 191, 2812, 43238].into_iter().enumerate() {
+		assert_eq!(board.perft(depth as u32 + 1), nodes);
+	}
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use common::{play, setup, square, standard, variant_game};
+use duckchess_common::{Player, Variant, Vec2};
+
+#[test]
+fn sees_where_pieces_can_move() {
+	let board = standard();
+	let visible = board.visible_squares(Player::White);
+	assert_eq!(visible.count(), 32);
+	assert!(visible.contains(square("e4")));
+	assert!(visible.contains(square("h3")));
+	assert!(!visible.contains(square("e5")));
+	assert_eq!(board.visible_squares(Player::Black).count(), 32);
+}
+
+#[test]
+fn view_hides_the_other_pieces() {
+	let mut board = variant_game(
+		Variant::FogOfWar,
+		setup("PPPPPPPP", "RNBQKBNR"),
+		setup("PPPPPPPP", "RNBKQBNR"),
+	);
+	play(&mut board, &[("e2", "e4"), ("d7", "d5")]);
+
+	let view = board.fog_view(Player::White);
+	assert_eq!(
+		view.to_fen(),
+		"8/8/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQ d6 0 2"
+	);
+	assert_eq!(view.get_king_position(Player::Black), Vec2::OFF_BOARD);
+	assert!(!view.moves.is_empty());
+	assert_eq!(view.history.len(), 1);
+	assert!(view.takebacks.is_empty());
+
+	// black isnt on turn, so the moves are hidden too
+	let view = board.fog_view(Player::Black);
+	assert!(view.moves.is_empty());
+	assert!(view.get_tile(square("e4")).piece.is_some());
+	assert!(view.get_tile(square("e2")).piece.is_none());
+	assert_eq!(view.get_king_position(Player::White), Vec2::OFF_BOARD);
+}
+
+#[test]
+fn view_hides_the_halfmove_clock() {
+	let mut board = variant_game(
+		Variant::FogOfWar,
+		setup("PPPPPPPP", "RNBQKBNR"),
+		setup("PPPPPPPP", "RNBKQBNR"),
+	);
+	play(&mut board, &[("g1", "f3"), ("b8", "c6")]);
+	assert_eq!(board.halfmove_clock, 2);
+	assert_eq!(board.fog_view(Player::White).halfmove_clock, 0);
+	assert_eq!(board.fog_view(Player::Black).halfmove_clock, 0);
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::{play, setup, square, variant_game};
+use duckchess_common::{Board, GameOutcome, MoveSelection, Player, Turn, Variant, WinReason};
+
+fn king_capture() -> Board {
+	variant_game(
+		Variant::KingCapture,
+		setup("PPPPPPPP", "RNBQKBNR"),
+		setup("PPPPPPPP", "RNBKQBNR"),
+	)
+}
+
+fn coordinates(board: &Board, from: &str, to: &str) -> Turn {
+	Turn {
+		game_id: board.id.clone(),
+		selection: MoveSelection::Coordinates {
+			from: square(from),
+			to: square(to),
+			promotion: None,
+		},
+	}
+}
+
+#[test]
+fn king_can_be_left_attacked_and_captured() {
+	let mut board = king_capture();
+	play(&mut board, &[("f2", "f3"), ("e7", "e5")]);
+	// ignores the threat of Qh4
+	play(&mut board, &[("a2", "a3"), ("d8", "h4")]);
+	assert_eq!(board.history[3].notation, "Qh4");
+	// a move that stays in check
+	let (_, outcome) = board
+		.evaluate_turn(&coordinates(&board, "b2", "b3"))
+		.unwrap();
+	assert_eq!(outcome, None);
+	let (_, outcome) = board
+		.evaluate_turn(&coordinates(&board, "h4", "e1"))
+		.unwrap();
+	assert_eq!(
+		outcome,
+		Some(GameOutcome::Win {
+			winner: Player::Black,
+			reason: WinReason::KingCapture,
+		})
+	);
+	assert_eq!(board.history[5].notation, "Qxe1#");
+}
+
+#[test]
+fn castling_into_attacked_squares() {
+	let mut board = Board::from_fen("4k3/8/8/8/8/8/6r1/4K2R w K - 0 1").unwrap();
+	let e1 = board
+		.move_pieces
+		.iter()
+		.position(|p| *p == square("e1"))
+		.unwrap();
+	assert!(board.moves[e1].iter().all(|m| m.to != square("g1")));
+	board.variant = Variant::KingCapture;
+	board.generate_moves(true);
+	let e1 = board
+		.move_pieces
+		.iter()
+		.position(|p| *p == square("e1"))
+		.unwrap();
+	assert!(board.moves[e1].iter().any(|m| m.to == square("g1")));
+}
+
+#[test]
+fn duck_chess_has_no_check() {
+	let mut board = Board::from_fen("4k3/8/8/8/8/8/4r3/4K2* w - - 0 1").unwrap();
+	let e1 = board
+		.move_pieces
+		.iter()
+		.position(|p| *p == square("e1"))
+		.unwrap();
+	// every king move is allowed, including the ones along the castle's rank
+	assert_eq!(board.moves[e1].len(), 5);
+	play(&mut board, &[("e1", "d1"), ("h1", "h2")]);
+	let (_, outcome) = board
+		.evaluate_turn(&coordinates(&board, "e2", "d2"))
+		.unwrap();
+	assert_eq!(outcome, None);
+	assert_eq!(board.history[2].notation, "Rd2");
+}
+
+#[test]
+fn perft() {
+	let mut board = king_capture();
+	for (depth, nodes) in [20, 400, 8902].into_iter().enumerate() {
+		assert_eq!(board.perft(depth as u32 + 1), nodes);
+	}
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::{play, setup, square, variant_fen, variant_game};
+use duckchess_common::{
+	Board, DrawReason, GameOutcome, MoveSelection, Player, Turn, Variant, WinReason,
+};
+
+#[test]
+fn reaching_the_center_wins() {
+	let mut board = variant_fen(Variant::KingOfTheHill, "4k3/8/8/8/8/2K5/8/8 w - - 0 1");
+	assert_eq!(board.outcome(), None);
+	let (_, outcome) = board
+		.evaluate_turn(&Turn {
+			game_id: board.id.clone(),
+			selection: MoveSelection::Coordinates {
+				from: square("c3"),
+				to: square("d4"),
+				promotion: None,
+			},
+		})
+		.unwrap();
+	assert_eq!(
+		outcome,
+		Some(GameOutcome::Win {
+			winner: Player::White,
+			reason: WinReason::KingOfTheHill,
+		})
+	);
+	assert_eq!(
+		outcome.unwrap().to_string(),
+		"white wins by reaching the hill"
+	);
+}
+
+#[test]
+fn only_counts_in_king_of_the_hill() {
+	let fen = "8/8/8/3K4/8/8/8/7k b - - 0 1";
+	assert_eq!(
+		Board::from_fen(fen).unwrap().outcome(),
+		Some(GameOutcome::Draw {
+			reason: DrawReason::InsufficientMaterial,
+		})
+	);
+	assert_eq!(
+		variant_fen(Variant::KingOfTheHill, fen).outcome(),
+		Some(GameOutcome::Win {
+			winner: Player::White,
+			reason: WinReason::KingOfTheHill,
+		})
+	);
+}
+
+#[test]
+fn attacked_center_squares_are_off_limits() {
+	let mut board = variant_game(
+		Variant::KingOfTheHill,
+		setup("PPPPPPPP", "RNBQKBNR"),
+		setup("PPPPPPPP", "RNBKQBNR"),
+	);
+	play(
+		&mut board,
+		&[
+			("e2", "e4"),
+			("e7", "e5"),
+			("e1", "e2"),
+			("d7", "d6"),
+			("e2", "d3"),
+			("g8", "f6"),
+		],
+	);
+	let d3 = board
+		.move_pieces
+		.iter()
+		.position(|p| *p == square("d3"))
+		.unwrap();
+	assert!(board.moves[d3].iter().all(|m| m.to != square("d4")));
+	assert_eq!(board.outcome(), None);
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::{setup, square};
+use duckchess_common::{
+	Board, BoardMap, Floor, GameStart, GameStartPlayer, MapError, Variant, Vec2,
+};
+
+#[test]
+fn parse_and_write() {
+	let map: BoardMap = "8/8/8/2#.o3/3~4/8/8/8".parse().unwrap();
+	assert_eq!(map.floor(square("c5")), Floor::Wall);
+	assert_eq!(map.floor(square("d5")), Floor::Light);
+	assert_eq!(map.floor(square("e5")), Floor::Hole);
+	assert_eq!(map.floor(square("d4")), Floor::Ice);
+	assert_eq!(map.to_string(), "8/8/8/2#1o3/3~4/8/8/8");
+	assert_eq!(BoardMap::default().to_string(), "8/8/8/8/8/8/8/8");
+}
+
+#[test]
+fn invalid_maps() {
+	assert_eq!("8/8/8".parse::<BoardMap>(), Err(MapError::InvalidRows));
+	assert_eq!(
+		"8/8/8/8/8/9/8/8".parse::<BoardMap>(),
+		Err(MapError::InvalidRows)
+	);
+	assert_eq!(
+		"13/13/13/13/13/13/13/13".parse::<BoardMap>(),
+		Err(MapError::InvalidRows)
+	);
+	assert_eq!(
+		"8/8/8/3x4/8/8/8/8".parse::<BoardMap>(),
+		Err(MapError::InvalidFloor('x'))
+	);
+	assert_eq!(
+		"8/#7/8/8/8/8/8/8".parse::<BoardMap>(),
+		Err(MapError::SetupRankNotClear)
+	);
+	assert_eq!(
+		"8/8/8/########/8/8/8/8".parse::<BoardMap>(),
+		Err(MapError::Unreachable)
+	);
+	// ice can be walked over
+	assert!("8/8/8/~~~~~~~~/8/8/8/8".parse::<BoardMap>().is_ok());
+	assert!("8/8/8/oooooooo/8/8/8/8".parse::<BoardMap>().is_err());
+}
+
+#[test]
+fn built_in_maps_are_fair() {
+	for (name, _) in BoardMap::BUILT_IN {
+		let map = BoardMap::built_in(name).unwrap();
+		let (width, height) = (map.width(), map.height());
+		for y in 0..height {
+			for x in 0..width {
+				let floor = |pos| match map.floor(pos) {
+					Floor::Light | Floor::Dark => None,
+					floor => Some(floor),
+				};
+				assert_eq!(
+					floor(Vec2(x, y)),
+					floor(Vec2(width - 1 - x, height - 1 - y)),
+					"{}",
+					name
+				);
+			}
+		}
+	}
+	assert!(BoardMap::built_in("nowhere").is_none());
+}
+
+#[test]
+fn game_on_a_map() {
+	let map = BoardMap::built_in("pillars").unwrap();
+	let game_start = GameStart {
+		white: GameStartPlayer {
+			id: "white".to_string(),
+			setup: setup("PPPPPPPP", "RNBQKBNR"),
+		},
+		black: GameStartPlayer {
+			id: "black".to_string(),
+			setup: setup("PPPPPPPP", "RNBKQBNR"),
+		},
+		game_id: "map".to_string(),
+		variant: Variant::Standard,
+		map,
+	};
+	let json = rocket::serde::json::serde_json::to_string(&game_start).unwrap();
+	assert!(json.contains("\"map\":\"8/8/8/2#2#2/2#2#2/8/8/8\""));
+
+	let board = Board::new(game_start);
+	assert_eq!(board.get_tile(square("c5")).floor, Floor::Wall);
+	assert!(board.board.walls().contains(square("f4")));
+	// the pillar on c4 stops the c pawn from double advancing
+	let i = board
+		.move_pieces
+		.iter()
+		.position(|p| *p == square("c2"))
+		.unwrap();
+	assert_eq!(board.moves[i].len(), 1);
+}
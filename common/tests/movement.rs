@@ -0,0 +1,215 @@
+mod common;
+
+use std::sync::LazyLock;
+
+use common::{play, setup, square, variant_fen};
+use duckchess_common::{
+	Board, BoardMap, BoardSetup, CustomPiece, CustomPieceError, MoveSelection, Movement,
+	MovementError, PieceType, Player, SetupPieceType, Turn, Variant,
+};
+use rocket::serde::json::serde_json;
+
+// the registry can only be filled once per process
+static PIECES: LazyLock<()> = LazyLock::new(|| {
+	let path = std::env::temp_dir().join(format!("custom_pieces_{}.json", std::process::id()));
+	std::fs::write(
+		&path,
+		r#"[
+			{"name": "nightrider", "letter": "S", "movement": "NN", "setupValue": 500},
+			{"name": "berolina", "letter": "E", "movement": "mfFcfW", "setupValue": 100}
+		]"#,
+	)
+	.unwrap();
+	CustomPiece::load(&path).unwrap();
+	std::fs::remove_file(path).unwrap();
+});
+
+fn targets(board: &Board, from: &str) -> Vec<String> {
+	let mut targets = board
+		.move_pieces
+		.iter()
+		.position(|p| *p == square(from))
+		.map_or(&[][..], |i| &board.moves[i])
+		.iter()
+		.map(|m| m.to.square_name(8))
+		.collect::<Vec<_>>();
+	targets.sort();
+	targets
+}
+
+#[test]
+fn parse_betza() {
+	assert!("K".parse::<Movement>().is_ok());
+	assert!("mfWcfF".parse::<Movement>().is_ok());
+	assert!("R4N".parse::<Movement>().is_ok());
+	assert_eq!(
+		"X".parse::<Movement>(),
+		Err(MovementError::InvalidCharacter('X'))
+	);
+	assert_eq!("Nm".parse::<Movement>(), Err(MovementError::MissingAtom));
+	assert_eq!("3".parse::<Movement>(), Err(MovementError::MissingAtom));
+	assert_eq!("W300".parse::<Movement>(), Err(MovementError::InvalidRange));
+	let movement: Movement = serde_json::from_str("\"NN\"").unwrap();
+	assert_eq!(serde_json::to_string(&movement).unwrap(), "\"NN\"");
+}
+
+#[test]
+fn built_in_pieces_use_movements() {
+	let board = Board::from_fen("k7/8/8/8/3Q4/8/8/7K w - - 0 1").unwrap();
+	let attacks = PieceType::Queen
+		.movement()
+		.attacks(Player::White, square("d4"), &board);
+	assert_eq!(attacks.count(), 27);
+	assert!(attacks.contains(square("h8")));
+}
+
+#[test]
+fn ranges_and_modifiers() {
+	// a rook that only goes two squares
+	let movement: Movement = "R2".parse().unwrap();
+	let board = Board::from_fen("k7/8/8/3p4/8/8/8/7K w - - 0 1").unwrap();
+	// the pawn on d5 is in the way
+	assert_eq!(
+		movement
+			.attacks(Player::White, square("d4"), &board)
+			.count(),
+		7
+	);
+	// forward only, mirrored for black
+	let movement: Movement = "fW".parse().unwrap();
+	assert!(
+		movement
+			.attacks(Player::White, square("d4"), &board)
+			.contains(square("d5"))
+	);
+	assert!(
+		movement
+			.attacks(Player::Black, square("d4"), &board)
+			.contains(square("d3"))
+	);
+	// moving only can't capture
+	let movement: Movement = "mW".parse().unwrap();
+	assert!(
+		movement
+			.attacks(Player::White, square("d4"), &board)
+			.is_empty()
+	);
+}
+
+#[test]
+fn custom_pieces() {
+	LazyLock::force(&PIECES);
+	let nightrider = CustomPiece::find("nightrider").unwrap();
+	assert_eq!(nightrider.letter, 'S');
+	assert!(CustomPiece::find("dragon").is_none());
+
+	let board = Board::from_fen("k7/8/8/8/8/8/8/S6K w - - 0 1").unwrap();
+	assert_eq!(board.to_fen(), "k7/8/8/8/8/8/8/S6K w - - 0 1");
+	assert_eq!(targets(&board, "a1"), ["b3", "c2", "c5", "d7", "e3", "g4"]);
+	let piece = board.get_tile(square("a1")).piece.clone().unwrap();
+	assert_eq!(
+		serde_json::to_string(&piece.piece_type).unwrap(),
+		r#"{"type":"custom","name":"nightrider"}"#
+	);
+
+	// the berolina pawn moves diagonally and captures straight ahead
+	let board = Board::from_fen("k7/8/8/3p4/4E3/8/8/7K w - - 0 1").unwrap();
+	assert_eq!(targets(&board, "e4"), ["f5"]);
+	let board = Board::from_fen("k7/8/8/4p3/4E3/8/8/7K w - - 0 1").unwrap();
+	assert_eq!(targets(&board, "e4"), ["d5", "e5", "f5"]);
+}
+
+#[test]
+fn custom_pieces_give_check() {
+	LazyLock::force(&PIECES);
+	let board = Board::from_fen("k7/8/8/8/2S5/8/8/7K b - - 0 1").unwrap();
+	assert!(board.is_in_check(Player::Black));
+	let board = Board::from_fen("k7/8/1P6/8/2S5/8/8/7K b - - 0 1").unwrap();
+	assert!(!board.is_in_check(Player::Black));
+
+	let mut board = Board::from_fen("k7/8/8/8/8/8/8/S6K w - - 0 1").unwrap();
+	play(&mut board, &[("a1", "c5")]);
+	assert_eq!(board.history[0].notation, "Sc5");
+	assert_eq!(board.hash, board.compute_hash());
+}
+
+#[test]
+fn custom_pieces_in_setups() {
+	LazyLock::force(&PIECES);
+	let map = BoardMap::default();
+	let mut rows = setup("PPPPPPPP", "RNBQKBNR").rows().to_vec();
+	rows[1][1] = Some(SetupPieceType::Custom {
+		name: "nightrider".to_string(),
+	});
+	assert!(!BoardSetup::new(rows.clone()).has_unknown_pieces());
+	assert!(BoardSetup::new(rows.clone()).is_valid_for(Variant::Standard, &map));
+	rows[1][1] = Some(SetupPieceType::Custom {
+		name: "dragon".to_string(),
+	});
+	assert!(BoardSetup::new(rows.clone()).has_unknown_pieces());
+	assert!(!BoardSetup::new(rows).is_valid_for(Variant::Standard, &map));
+}
+
+#[test]
+fn registering_twice() {
+	LazyLock::force(&PIECES);
+	let piece = |letter| CustomPiece {
+		name: "wazir".to_string(),
+		letter,
+		movement: "W".parse().unwrap(),
+		setup_value: 100,
+	};
+	assert!(matches!(
+		CustomPiece::register(vec![piece('Q')]),
+		Err(CustomPieceError::InvalidLetter('Q'))
+	));
+	assert!(matches!(
+		CustomPiece::register(vec![piece('W'), piece('V')]),
+		Err(CustomPieceError::DuplicateName(_))
+	));
+	assert!(matches!(
+		CustomPiece::register(vec![piece('W')]),
+		Err(CustomPieceError::AlreadyLoaded)
+	));
+}
+
+#[test]
+fn custom_pieces_in_the_reserve() {
+	LazyLock::force(&PIECES);
+	let nightrider = PieceType::Custom {
+		name: "nightrider".to_string(),
+	};
+	let berolina = PieceType::Custom {
+		name: "berolina".to_string(),
+	};
+	let mut board = variant_fen(Variant::Crazyhouse, "4k3/3e4/8/3s4/8/8/8/3QK3 w - - 0 1");
+	play(
+		&mut board,
+		&[("d1", "d5"), ("e8", "f8"), ("d5", "d7"), ("f8", "g8")],
+	);
+	assert_eq!(
+		board.get_reserve(Player::White),
+		[nightrider.clone(), berolina.clone()]
+	);
+	assert_eq!(board.hash, board.compute_hash());
+	board
+		.evaluate_turn(&Turn {
+			game_id: board.id.clone(),
+			selection: MoveSelection::Drop {
+				piece: berolina.clone(),
+				to: square("e4"),
+			},
+		})
+		.unwrap();
+	assert_eq!(
+		board
+			.get_tile(square("e4"))
+			.piece
+			.as_ref()
+			.unwrap()
+			.piece_type,
+		berolina
+	);
+	assert_eq!(board.get_reserve(Player::White), [nightrider]);
+	assert_eq!(board.hash, board.compute_hash());
+}
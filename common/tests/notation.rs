@@ -0,0 +1,123 @@
+mod common;
+
+use common::{game, play, setup, square, standard};
+use duckchess_common::{Board, MoveSelection, PieceType, Player, Turn};
+
+fn history(board: &Board) -> Vec<&str> {
+	board
+		.history
+		.iter()
+		.map(|played| played.notation.as_str())
+		.collect()
+}
+
+#[test]
+fn pieces_captures_and_castling() {
+	let mut board = standard();
+	play(
+		&mut board,
+		&[
+			("e2", "e4"),
+			("e7", "e5"),
+			("g1", "f3"),
+			("b8", "c6"),
+			("f1", "b5"),
+			("a7", "a6"),
+			("b5", "c6"),
+			("d7", "c6"),
+			("e1", "g1"),
+		],
+	);
+	assert_eq!(
+		history(&board),
+		["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Bxc6", "dxc6", "O-O"]
+	);
+	assert_eq!(board.history[1].player, Player::Black);
+	assert_eq!(board.history[8].mov.from, square("e1"));
+}
+
+#[test]
+fn check_and_checkmate() {
+	let mut board = standard();
+	play(&mut board, &[("e2", "e4"), ("f7", "f6"), ("d1", "h5")]);
+	assert_eq!(board.history[2].notation, "Qh5+");
+	let mut board = standard();
+	play(
+		&mut board,
+		&[
+			("e2", "e4"),
+			("e7", "e5"),
+			("d1", "h5"),
+			("b8", "c6"),
+			("f1", "c4"),
+			("g8", "f6"),
+			("h5", "f7"),
+		],
+	);
+	assert_eq!(board.history[6].notation, "Qxf7#");
+}
+
+#[test]
+fn disambiguation() {
+	let mut board = standard();
+	play(
+		&mut board,
+		&[
+			("d2", "d4"),
+			("d7", "d5"),
+			("g1", "f3"),
+			("g8", "f6"),
+			("b1", "d2"),
+		],
+	);
+	assert_eq!(board.history[4].notation, "Nbd2");
+	let mut board = Board::from_fen("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1").unwrap();
+	play(&mut board, &[("a1", "a3")]);
+	assert_eq!(board.history[0].notation, "R1a3");
+}
+
+#[test]
+fn en_passant() {
+	let mut board = standard();
+	play(
+		&mut board,
+		&[
+			("e2", "e4"),
+			("a7", "a6"),
+			("e4", "e5"),
+			("d7", "d5"),
+			("e5", "d6"),
+		],
+	);
+	assert_eq!(board.history[4].notation, "exd6");
+}
+
+#[test]
+fn promotion() {
+	let mut board = Board::from_fen("8/P7/8/8/8/8/8/4K2k w - - 0 1").unwrap();
+	board
+		.evaluate_turn(&Turn {
+			game_id: board.id.clone(),
+			selection: MoveSelection::Coordinates {
+				from: square("a7"),
+				to: square("a8"),
+				promotion: Some(PieceType::Queen),
+			},
+		})
+		.unwrap();
+	assert_eq!(board.history[0].notation, "a8=Q+");
+}
+
+#[test]
+fn ducks() {
+	let mut board = game(setup("PPPDDPPP", "RNBQKBNR"), setup("PPPPPPPP", "RNBKQBNR"));
+	play(&mut board, &[("d2", "d4"), ("e7", "e6"), ("e2", "e3")]);
+	assert_eq!(history(&board), ["Ddd4", "e6", "Dee3"]);
+}
+
+#[test]
+fn queenside_castling_with_king_on_d_file() {
+	let mut board = game(setup("PPPPPPPP", "R..K...R"), setup("PPPPPPPP", "RNBKQBNR"));
+	play(&mut board, &[("d1", "b1")]);
+	assert_eq!(board.history[0].notation, "O-O-O");
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use common::{game, setup, standard};
+use duckchess_common::Board;
+
+fn assert_perft(board: &mut Board, expected: &[u64]) {
+	for (depth, nodes) in expected.iter().enumerate() {
+		assert_eq!(board.perft(depth as u32 + 1), *nodes, "depth {}", depth + 1);
+	}
+}
+
+#[test]
+fn standard_position() {
+	// same as regular chess
+	assert_perft(&mut standard(), &[20, 400, 8902, 197281]);
+}
+
+#[test]
+fn perft_leaves_board_unchanged() {
+	let mut board = standard();
+	let hash = board.hash;
+	let moves = board.moves.clone();
+	board.perft(3);
+	assert_eq!(board.hash, hash);
+	assert_eq!(format!("{:?}", board.moves), format!("{:?}", moves));
+}
+
+// there's no other engine with owned ducks to check against, so only the first ply is counted,
+// which can be done by hand: 6 pawns with 2 moves, 2 knights with 2 and 2 ducks with 32 empty squares
+#[test]
+fn ducks() {
+	let mut board = game(setup("PPPDDPPP", "RNBQKBNR"), setup("PPPPPPPP", "RNBKQBNR"));
+	assert_perft(&mut board, &[6 * 2 + 2 * 2 + 2 * 32]);
+	// ducks in place of the a and h pawns leave the same moves
+	let mut board = game(setup("DPPPPPPD", "RNBQKBNR"), setup("PPDPPDPP", "RNBKQBNR"));
+	assert_perft(&mut board, &[6 * 2 + 2 * 2 + 2 * 32]);
+}
+
+// castling towards the a file with the king on d is castling towards the h file mirrored
+#[test]
+fn castling_with_king_on_d_file() {
+	let mirrored = |row: &str| row.chars().rev().collect::<String>();
+	let (white, black) = (("PPPPPPPP", "R..K...R"), ("PPPPPPPP", "R...K..R"));
+	let mut board = game(setup(white.0, white.1), setup(black.0, black.1));
+	let mut mirror = game(
+		setup(&mirrored(white.0), &mirrored(white.1)),
+		setup(&mirrored(black.0), &mirrored(black.1)),
+	);
+	for depth in 1..=3 {
+		assert_eq!(board.perft(depth), mirror.perft(depth), "depth {}", depth);
+	}
+}
+
+// the well known test positions from https://www.chessprogramming.org/Perft_Results.
+// they cover castling, en passant and promotions
+#[test]
+fn kiwipete() {
+	let mut board =
+		Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+			.unwrap();
+	assert_perft(&mut board, &[48, 2039, 97862]);
+}
+
+#[test]
+fn position_3() {
+	let mut board = Board::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap();
+	assert_perft(&mut board, &[14, 191, 2812, 43238]);
+}
+
+#[test]
+fn position_4() {
+	let mut board =
+		Board::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1")
+			.unwrap();
+	assert_perft(&mut board, &[6, 264, 9467]);
+	// the same position with the colours swapped
+	let mut board =
+		Board::from_fen("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1")
+			.unwrap();
+	assert_perft(&mut board, &[6, 264, 9467]);
+}
+
+#[test]
+fn position_5() {
+	let mut board =
+		Board::from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8").unwrap();
+	assert_perft(&mut board, &[44, 1486, 62379]);
+}
+
+#[test]
+fn position_6() {
+	let mut board =
+		Board::from_fen("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10")
+			.unwrap();
+	assert_perft(&mut board, &[46, 2079, 89890]);
+}
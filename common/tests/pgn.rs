@@ -0,0 +1,67 @@
+mod common;
+
+use common::{game, play, setup};
+use duckchess_common::{
+	ChessClock, GameOutcome, GameRecord, GameStart, GameStartPlayer, Player, WinReason,
+};
+
+#[test]
+fn finished_game() {
+	let white = setup("PPPDDPPP", "RNBQKBNR");
+	let black = setup("PPPPPPPP", "RNBKQBNR");
+	let mut board = game(white.clone(), black.clone());
+	play(
+		&mut board,
+		&[
+			("e2", "a3"),
+			("e7", "e5"),
+			("f1", "c4"),
+			("b8", "c6"),
+			("d1", "h5"),
+			("g8", "f6"),
+			("h5", "f7"),
+		],
+	);
+	let outcome = GameOutcome::Win {
+		winner: Player::White,
+		reason: WinReason::Checkmate,
+	};
+	let game_start = GameStart {
+		white: GameStartPlayer {
+			id: "white".to_string(),
+			setup: white,
+		},
+		black: GameStartPlayer {
+			id: "black".to_string(),
+			setup: black,
+		},
+		game_id: "perft".to_string(),
+		variant: Default::default(),
+		map: Default::default(),
+	};
+	let mut record = GameRecord::new(game_start, &board, outcome, Some(ChessClock::new()));
+	// 2025-03-04
+	record.date = 1741046400;
+	assert_eq!(
+		record.to_pgn(),
+		r#"[Event "Duck Chess game"]
+[Site "duckchess"]
+[Date "2025.03.04"]
+[Round "-"]
+[White "white"]
+[Black "black"]
+[Result "1-0"]
+[GameId "perft"]
+[WhiteSetup "PPPDDPPP/RNBQKBNR"]
+[BlackSetup "PPPPPPPP/RNBKQBNR"]
+[SetUp "1"]
+[FEN "rnbqkbnr/pppppppp/8/8/8/8/PPPDDPPP/RNBQKBNR w KQkq - 0 1"]
+[TimeControl "600"]
+[WhiteClock "0:10:00"]
+[BlackClock "0:10:00"]
+[Termination "white wins by checkmate"]
+
+1. Dea3 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7# 1-0
+"#
+	);
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use common::{play, setup, square, standard, variant_game};
+use duckchess_common::{Board, DrawReason, GameOutcome, MoveSelection, Turn, Variant, Vec2};
+use rocket::serde::json::{Value, serde_json};
+
+#[test]
+fn boards_stored_before_hashing_still_load() {
+	let mut board = standard();
+	play(&mut board, &[("e2", "e4")]);
+	let mut json = serde_json::to_value(&board).unwrap();
+	let Value::Object(fields) = &mut json else {
+		panic!("boards are objects");
+	};
+	for field in ["hash", "repetitions", "halfmoveClock", "fullmoveNumber"] {
+		assert!(fields.remove(field).is_some(), "{}", field);
+	}
+	let board: Board = serde_json::from_value(json).unwrap();
+	assert_eq!(board.hash, 0);
+	assert!(board.repetitions.is_empty());
+	assert_eq!(board.halfmove_clock, 0);
+	assert_eq!(board.fullmove_number, 1);
+}
+
+fn turn(board: &mut Board, from: Vec2, to: &str) -> Option<GameOutcome> {
+	board
+		.evaluate_turn(&Turn {
+			game_id: board.id.clone(),
+			selection: MoveSelection::Coordinates {
+				from,
+				to: square(to),
+				promotion: None,
+			},
+		})
+		.unwrap()
+		.1
+}
+
+const REPETITION: Option<GameOutcome> = Some(GameOutcome::Draw {
+	reason: DrawReason::Repetition,
+});
+
+#[test]
+fn threefold_repetition() {
+	let mut board = standard();
+	let knights = [("g1", "f3"), ("g8", "f6"), ("f3", "g1"), ("f6", "g8")];
+	// the starting position counts as the first occurrence
+	for _ in 0..2 {
+		for (i, (from, to)) in knights.iter().enumerate() {
+			let outcome = turn(&mut board, square(from), to);
+			if board.repetitions[&board.hash] == 3 {
+				assert_eq!(i, 3);
+				assert_eq!(outcome, REPETITION);
+			} else {
+				assert_eq!(outcome, None);
+			}
+		}
+	}
+	assert_eq!(board.repetitions[&board.hash], 3);
+}
+
+#[test]
+fn repetition_with_the_duck() {
+	let mut board = variant_game(
+		Variant::DuckChess,
+		setup("PPPPPPPP", "RNBQKBNR"),
+		setup("PPPPPPPP", "RNBKQBNR"),
+	);
+	play(&mut board, &[("g1", "f3")]);
+	turn(&mut board, Vec2::OFF_BOARD, "a3");
+	play(&mut board, &[("g8", "f6"), ("a3", "a6")]);
+	play(&mut board, &[("f3", "g1"), ("a6", "a3")]);
+	play(&mut board, &[("f6", "g8"), ("a3", "a6")]);
+	// the pieces are back with the duck on a6, and every position since the duck was placed
+	// has occurred once
+	for (from, to) in [
+		("g1", "f3"),
+		("a6", "a3"),
+		("g8", "f6"),
+		("a3", "a6"),
+		("f3", "g1"),
+		("a6", "a3"),
+		("f6", "g8"),
+		("a3", "a6"),
+	] {
+		assert_eq!(turn(&mut board, square(from), to), None);
+	}
+	// the knight move isnt a full turn, the duck move after it repeats a position for the third time
+	assert_eq!(turn(&mut board, square("g1"), "f3"), None);
+	assert_eq!(turn(&mut board, square("a6"), "a3"), REPETITION);
+}
+
+#[test]
+fn hash_matches_after_make_unmake_and_fen() {
+	let mut board =
+		Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+			.unwrap();
+	play(&mut board, &[("a2", "a4")]);
+	let hash = board.hash;
+	assert_eq!(hash, board.compute_hash());
+	assert_eq!(Board::from_fen(&board.to_fen()).unwrap().hash, hash);
+	for mov in board.moves.clone().into_iter().flatten() {
+		let undo = board.make_move(&mov);
+		assert_eq!(board.hash, board.compute_hash(), "{:?}", mov);
+		assert_eq!(
+			Board::from_fen(&board.to_fen()).unwrap().hash,
+			board.hash,
+			"{:?}",
+			mov
+		);
+		board.unmake_move(undo);
+		assert_eq!(board.hash, hash);
+	}
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use common::{play, square, standard};
+use duckchess_common::{Board, MoveSelection, PieceType, Turn};
+use rocket::serde::json::serde_json;
+
+// plays the moves one at a time, then takes them all back checking every position on the way
+fn round_trip(mut board: Board, moves: &[(&str, &str)]) {
+	let mut positions = vec![];
+	for mov in moves {
+		positions.push((board.to_fen(), board.hash, board.repetitions.clone()));
+		play(&mut board, &[*mov]);
+	}
+	while let Some((fen, hash, repetitions)) = positions.pop() {
+		let played = board.takeback().unwrap();
+		assert_eq!(played.mov.from, square(moves[positions.len()].0));
+		assert_eq!(board.to_fen(), fen);
+		assert_eq!(board.hash, hash);
+		assert_eq!(board.hash, board.compute_hash());
+		assert_eq!(board.repetitions, repetitions);
+	}
+	assert!(board.takeback().is_none());
+	assert!(board.history.is_empty());
+}
+
+#[test]
+fn castling_and_en_passant() {
+	round_trip(
+		standard(),
+		&[
+			("e2", "e4"),
+			("g8", "f6"),
+			("e4", "e5"),
+			("d7", "d5"),
+			("e5", "d6"),
+			("e7", "d6"),
+			("g1", "f3"),
+			("f8", "e7"),
+			("f1", "c4"),
+			("e8", "g8"),
+			("e1", "g1"),
+		],
+	);
+}
+
+#[test]
+fn repetitions() {
+	round_trip(
+		standard(),
+		&[
+			("g1", "f3"),
+			("g8", "f6"),
+			("f3", "g1"),
+			("f6", "g8"),
+			("g1", "f3"),
+			("e7", "e5"),
+			("f3", "e5"),
+		],
+	);
+}
+
+#[test]
+fn promotion() {
+	let mut board = Board::from_fen("8/P7/8/8/8/8/8/4K2k w - - 0 1").unwrap();
+	let fen = board.to_fen();
+	board
+		.evaluate_turn(&Turn {
+			game_id: board.id.clone(),
+			selection: MoveSelection::Coordinates {
+				from: square("a7"),
+				to: square("a8"),
+				promotion: Some(PieceType::Knight),
+			},
+		})
+		.unwrap();
+	board.takeback().unwrap();
+	assert_eq!(board.to_fen(), fen);
+	// the moves for the restored position are generated again
+	assert!(board.move_pieces.contains(&square("a7")));
+}
+
+#[test]
+fn undo_stack_is_serialized_on_its_own() {
+	let mut board = standard();
+	play(&mut board, &[("e2", "e4"), ("d7", "d5")]);
+	let json = serde_json::to_string(&board).unwrap();
+	assert!(!json.contains("takebacks"));
+	let undo = serde_json::to_string(&board.takebacks).unwrap();
+	let mut board: Board = serde_json::from_str(&json).unwrap();
+	assert!(board.takeback().is_none());
+	board.takebacks = serde_json::from_str(&undo).unwrap();
+	board.takeback().unwrap();
+	assert_eq!(
+		board.to_fen(),
+		"rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+	);
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use common::{play, square};
+use duckchess_common::{
+	Board, Floor, GameOutcome, MoveSelection, Player, Turn, Variant, WinReason,
+};
+
+fn terrain(fen: &str, floors: &[(&str, Floor)]) -> Board {
+	let mut board = Board::from_fen(fen).unwrap();
+	for (name, floor) in floors {
+		board.board.set_floor(square(name), *floor);
+	}
+	board.generate_moves(true);
+	board
+}
+
+fn destinations(board: &Board, from: &str) -> Vec<String> {
+	let i = board
+		.move_pieces
+		.iter()
+		.position(|p| *p == square(from))
+		.unwrap();
+	let mut squares = board.moves[i]
+		.iter()
+		.map(|m| m.to.square_name(8))
+		.collect::<Vec<_>>();
+	squares.sort();
+	squares
+}
+
+#[test]
+fn walls_block_movement() {
+	let board = terrain(
+		"4k3/8/8/8/8/8/8/RN2K3 w - - 0 1",
+		&[("a4", Floor::Wall), ("c3", Floor::Wall)],
+	);
+	assert_eq!(destinations(&board, "a1"), ["a2", "a3"]);
+	// knights jump over walls but cant land on them
+	assert_eq!(destinations(&board, "b1"), ["a3", "d2"]);
+}
+
+#[test]
+fn walls_block_attacks() {
+	let fen = "4k3/8/8/8/8/8/8/4RK2 b - - 0 1";
+	assert!(terrain(fen, &[]).is_in_check(Player::Black));
+	assert!(!terrain(fen, &[("e4", Floor::Wall)]).is_in_check(Player::Black));
+}
+
+#[test]
+fn holes_remove_pieces() {
+	let mut board = terrain(
+		"4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+		&[("a5", Floor::Hole), ("d1", Floor::Hole)],
+	);
+	// pieces can slide over holes, and the king wont step in
+	assert_eq!(
+		destinations(&board, "a1"),
+		["a2", "a3", "a4", "a5", "a6", "a7", "a8", "b1", "c1", "d1"]
+	);
+	assert!(!destinations(&board, "e1").contains(&"d1".to_string()));
+	play(&mut board, &[("a1", "a5")]);
+	assert_eq!(board.to_fen(), "4k3/8/8/8/8/8/8/4K3 b - - 1 1");
+	assert_eq!(board.hash, board.compute_hash());
+	board.takeback().unwrap();
+	assert!(board.get_tile(square("a1")).piece.is_some());
+}
+
+#[test]
+fn falling_in_loses_without_check() {
+	let mut board = terrain("4k3/8/8/8/8/8/8/4K3 w - - 0 1", &[("d1", Floor::Hole)]);
+	board.variant = Variant::KingCapture;
+	board.generate_moves(true);
+	let (_, outcome) = board
+		.evaluate_turn(&Turn {
+			game_id: board.id.clone(),
+			selection: MoveSelection::Coordinates {
+				from: square("e1"),
+				to: square("d1"),
+				promotion: None,
+			},
+		})
+		.unwrap();
+	assert_eq!(
+		outcome,
+		Some(GameOutcome::Win {
+			winner: Player::Black,
+			reason: WinReason::KingCapture,
+		})
+	);
+}
+
+#[test]
+fn sliders_cant_stop_on_ice() {
+	let board = terrain(
+		"4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+		&[("a3", Floor::Ice), ("a4", Floor::Ice), ("d1", Floor::Ice)],
+	);
+	assert_eq!(
+		destinations(&board, "a1"),
+		["a2", "a5", "a6", "a7", "a8", "b1", "c1"]
+	);
+	// the king only moves one square, so it can stand on ice
+	assert!(destinations(&board, "e1").contains(&"d1".to_string()));
+}
+
+#[test]
+fn grasshoppers_and_ice() {
+	// landing on empty ice isnt allowed
+	let board = terrain("k7/8/8/8/3p4/3G4/3P4/7K w - - 0 1", &[("d5", Floor::Ice)]);
+	assert!(!destinations(&board, "d3").contains(&"d5".to_string()));
+	// but capturing there is, so a king on ice is still in check
+	let fen = "3k4/3p4/8/8/3G4/8/8/7K b - - 0 1";
+	assert!(terrain(fen, &[("d8", Floor::Ice)]).is_in_check(Player::Black));
+	let board = terrain("3k4/3p4/8/8/3G4/8/8/7K w - - 0 1", &[("d8", Floor::Ice)]);
+	assert!(destinations(&board, "d4").contains(&"d8".to_string()));
+}
+
+#[test]
+fn ducks_cant_be_placed_on_terrain() {
+	let mut board = terrain(
+		"4k3/8/8/8/8/8/8/4K2* w - - 0 1",
+		&[("a3", Floor::Wall), ("b3", Floor::Hole), ("c3", Floor::Ice)],
+	);
+	play(&mut board, &[("e1", "e2")]);
+	let ducks = destinations(&board, "h1");
+	assert!(!ducks.contains(&"a3".to_string()));
+	assert!(!ducks.contains(&"b3".to_string()));
+	assert!(ducks.contains(&"c3".to_string()));
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use common::{play, variant_fen};
+use duckchess_common::{Board, GameOutcome, Player, TurnStart, Variant, WinReason};
+
+#[test]
+fn counts_checks() {
+	let mut board = variant_fen(Variant::ThreeCheck, "4k3/8/8/8/8/8/8/4K2Q w - - 0 1");
+	play(&mut board, &[("h1", "h5")]);
+	assert_eq!(board.get_checks(Player::White), 1);
+	assert_eq!(board.get_checks(Player::Black), 0);
+	assert_eq!(board.history[0].notation, "Qh5+");
+	assert_eq!(TurnStart::new(&board).checks, [1, 0]);
+	assert_eq!(board.outcome(), None);
+
+	play(&mut board, &[("e8", "d8")]);
+	assert_eq!(board.checks, [1, 0]);
+}
+
+#[test]
+fn takeback_restores_checks() {
+	let mut board = variant_fen(Variant::ThreeCheck, "4k3/8/8/8/8/8/8/4K2Q w - - 0 1");
+	let hash = board.hash;
+	play(&mut board, &[("h1", "h5")]);
+	board.takeback().unwrap();
+	assert_eq!(board.checks, [0, 0]);
+	assert_eq!(board.hash, hash);
+}
+
+#[test]
+fn third_check_wins() {
+	let mut board = variant_fen(Variant::ThreeCheck, "4k3/8/8/8/8/8/8/4K2Q w - - 0 1");
+	play(
+		&mut board,
+		&[("h1", "h5"), ("e8", "d8"), ("h5", "h4"), ("d8", "c8")],
+	);
+	assert_eq!(board.outcome(), None);
+	play(&mut board, &[("h4", "h3")]);
+	assert_eq!(board.checks, [3, 0]);
+	let outcome = board.outcome();
+	assert_eq!(
+		outcome,
+		Some(GameOutcome::Win {
+			winner: Player::White,
+			reason: WinReason::ThreeChecks,
+		})
+	);
+	assert_eq!(outcome.unwrap().to_string(), "white wins by three checks");
+}
+
+#[test]
+fn only_counts_in_three_check() {
+	let mut board = Board::from_fen("4k3/8/8/8/8/8/8/4K2Q w - - 0 1").unwrap();
+	play(&mut board, &[("h1", "h5")]);
+	assert_eq!(board.checks, [0, 0]);
+}
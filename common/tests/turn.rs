@@ -0,0 +1,149 @@
+mod common;
+
+use common::{game, play, setup, square, standard, variant_fen};
+use duckchess_common::{
+	Board, MoveSelection, MoveType, PieceType, Player, Turn, TurnError, TurnStart, Variant,
+};
+
+fn coordinates(from: &str, to: &str, promotion: Option<PieceType>) -> Turn {
+	Turn {
+		game_id: "test".to_string(),
+		selection: MoveSelection::Coordinates {
+			from: square(from),
+			to: square(to),
+			promotion,
+		},
+	}
+}
+
+#[test]
+fn coordinates_resolve_to_the_legal_move() {
+	let mut board = standard();
+	let (moves, outcome) = board.evaluate_turn(&coordinates("g1", "f3", None)).unwrap();
+	assert_eq!(moves.len(), 1);
+	assert_eq!(moves[0].to, square("f3"));
+	assert_eq!(outcome, None);
+}
+
+#[test]
+fn illegal_coordinates_are_rejected() {
+	let mut board = standard();
+	let hash = board.hash;
+	assert_eq!(
+		board
+			.evaluate_turn(&coordinates("e2", "e5", None))
+			.unwrap_err(),
+		TurnError::IllegalMove
+	);
+	assert_eq!(
+		board
+			.evaluate_turn(&coordinates("e7", "e5", None))
+			.unwrap_err(),
+		TurnError::IllegalMove
+	);
+	assert_eq!(board.hash, hash);
+}
+
+#[test]
+fn stale_indices_are_rejected() {
+	let mut board = standard();
+	let turn = Turn {
+		game_id: "test".to_string(),
+		selection: MoveSelection::Index {
+			piece_idx: board.moves.len(),
+			move_idx: 0,
+		},
+	};
+	assert_eq!(
+		board.evaluate_turn(&turn).unwrap_err(),
+		TurnError::NoSuchMove
+	);
+}
+
+#[test]
+fn promotion_piece_is_required() {
+	let mut board = game(setup("P.......", "....K..."), setup("........", "...K...."));
+	play(
+		&mut board,
+		&[
+			("a2", "a4"),
+			("e8", "d8"),
+			("a4", "a5"),
+			("d8", "e8"),
+			("a5", "a6"),
+			("e8", "d8"),
+			("a6", "a7"),
+			("d8", "e8"),
+		],
+	);
+	assert_eq!(
+		board
+			.evaluate_turn(&coordinates("a7", "a8", None))
+			.unwrap_err(),
+		TurnError::MissingPromotion
+	);
+	assert_eq!(
+		board
+			.evaluate_turn(&coordinates("a7", "a8", Some(PieceType::King)))
+			.unwrap_err(),
+		TurnError::InvalidPromotion
+	);
+	let (moves, _) = board
+		.evaluate_turn(&coordinates("a7", "a8", Some(PieceType::Knight)))
+		.unwrap();
+	assert!(matches!(
+		moves[0].move_type,
+		MoveType::Promotion {
+			into: PieceType::Knight
+		}
+	));
+}
+
+#[test]
+fn promotion_piece_is_rejected_for_normal_moves() {
+	let mut board = standard();
+	assert_eq!(
+		board
+			.evaluate_turn(&coordinates("e2", "e4", Some(PieceType::Queen)))
+			.unwrap_err(),
+		TurnError::InvalidPromotion
+	);
+}
+
+#[test]
+fn turn_start_shows_checks() {
+	let mut board = standard();
+	let turn_start = TurnStart::new(&board);
+	assert!(!turn_start.in_check);
+	assert!(turn_start.last_move.is_none());
+
+	play(
+		&mut board,
+		&[
+			("e2", "e4"),
+			("f7", "f6"),
+			("d2", "d4"),
+			("g7", "g5"),
+			("d1", "h5"),
+		],
+	);
+	let turn_start = TurnStart::new(&board);
+	assert!(turn_start.in_check);
+	assert_eq!(turn_start.checkers, [square("h5")]);
+	let last_move = turn_start.last_move.unwrap();
+	assert_eq!(last_move.player, Player::White);
+	assert_eq!(last_move.notation, "Qh5#");
+
+	// a double check
+	let board = Board::from_fen("4r1k1/8/8/8/8/5n2/8/4K3 w - - 0 1").unwrap();
+	let turn_start = TurnStart::new(&board);
+	assert_eq!(turn_start.checkers.len(), 2);
+}
+
+#[test]
+fn turn_start_without_check() {
+	let board = variant_fen(Variant::KingCapture, "4r1k1/8/8/8/8/8/8/4K3 w - - 0 1");
+	let turn_start = TurnStart::new(&board);
+	assert!(!turn_start.in_check);
+	assert!(turn_start.checkers.is_empty());
+}
@@ -13,6 +13,9 @@ pub struct CustomConfig {
 	pub cors_allow_all_origins: bool,
 	#[serde(default = "get_default_cookies_same_site")]
 	pub cookies_same_site: SameSiteConfig,
+	// json file with the pieces defined by the server operator, see CustomPiece::load
+	#[serde(default)]
+	pub custom_pieces_file: Option<String>,
 }
 
 #[derive(Debug, Clone)]
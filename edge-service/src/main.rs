@@ -4,6 +4,7 @@ mod util;
 
 use crate::config::CustomConfig;
 use crate::util::close_socket;
+use duckchess_common::{CustomPiece, WinReason};
 use play_socket::{PlaySocket, PlaySocketState};
 use redis::streams::{StreamKey, StreamReadOptions, StreamReadReply};
 use redis::{AsyncCommands, RedisFuture};
@@ -49,7 +50,7 @@ async fn play(
 			let mut redis = socket_state.redis.clone();
 			let close_message;
 			let allow_reconnect;
-			let surrender;
+			let forfeit;
 			'main_loop: loop {
 				let last_id;
 				let stream_key;
@@ -80,17 +81,17 @@ async fn play(
 					Some(Ok(message)) = socket_state.socket.next() => {
 						match message {
 							ws::Message::Text(text) => {
-								if let Some(msg) = socket_state.handle_message(&text).await {
+								if let Some((msg, reason)) = socket_state.handle_message(&text).await {
 									close_message = msg;
 									allow_reconnect = false;
-									surrender = true;
+									forfeit = reason;
 									break;
 								}
 							}
 							ws::Message::Close(_) => {
 								close_message = "client disconnected";
 								allow_reconnect = true;
-								surrender = true;
+								forfeit = Some(WinReason::Abandonment);
 								break;
 							}
 							_ => {}
@@ -102,7 +103,7 @@ async fn play(
 								if let Some(msg) = socket_state.process_stream_id(message).await {
 									close_message = msg;
 									allow_reconnect = false;
-									surrender = false;
+									forfeit = None;
 									break 'main_loop;
 								}
 							}
@@ -112,25 +113,25 @@ async fn play(
 					_ = &mut end => {
 						close_message = "server closed";
 						allow_reconnect = true;
-						surrender = false;
+						forfeit = None;
 						break;
 					}
 					else => {
 						close_message = "client disconnected";
 						allow_reconnect = true;
-						surrender = true;
+						forfeit = Some(WinReason::Abandonment);
 						break;
 					}
 				}
 				if let Some(msg) = socket_state.tick().await {
 					close_message = msg;
 					allow_reconnect = false;
-					surrender = true;
+					forfeit = Some(WinReason::Timeout);
 					break;
 				}
 			}
 			socket_state
-				.disconnected(&close_message, allow_reconnect, surrender)
+				.disconnected(&close_message, allow_reconnect, forfeit)
 				.await;
 			Ok(())
 		})
@@ -167,6 +168,29 @@ async fn login(
 	Json(id)
 }
 
+// pgn of a finished game, kept by the game service for a while after the game ends.
+// the game service only writes it once the game is over, so games in progress cant be read
+#[get("/pgn/<game_id>")]
+async fn pgn(
+	game_id: &str,
+	mut redis: Connection<RedisPool>,
+	cookies: &CookieJar<'_>,
+) -> Result<Option<String>, ErrorResponse> {
+	if cookies.get_private("user_id").is_none() {
+		return Err(ErrorResponse::Unauthorized(()));
+	}
+	Ok(redis
+		.get(format!("pgn:{}", game_id))
+		.await
+		.expect("redis error"))
+}
+
+// the custom pieces players can use in their setups
+#[get("/pieces")]
+fn pieces() -> Json<&'static [CustomPiece]> {
+	Json(CustomPiece::all())
+}
+
 #[derive(Responder)]
 enum ErrorResponse {
 	#[response(status = 401)]
@@ -184,9 +208,12 @@ struct PostgresPool(sqlx::PgPool);
 #[rocket::main]
 async fn main() -> Result<(), Box<dyn Error>> {
 	let partial_rocket = rocket::build()
-		.mount("/", routes![play, login])
+		.mount("/", routes![play, login, pgn, pieces])
 		.attach(AdHoc::config::<CustomConfig>());
 	let custom_config = partial_rocket.figment().extract::<CustomConfig>().unwrap();
+	if let Some(path) = &custom_config.custom_pieces_file {
+		CustomPiece::load(path)?;
+	}
 	let allowed_origins = if custom_config.cors_allow_all_origins {
 		AllowedOrigins::all()
 	} else {
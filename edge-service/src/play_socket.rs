@@ -1,8 +1,9 @@
 use std::time::Duration;
 
 use duckchess_common::{
-	Board, BoardSetup, ChatMessage, ChessClock, GAME_LENGTH, GameStart, GameStartPlayer, Move,
-	PlayRequest, PlayResponse, Player, Turn, TurnStart,
+	Board, BoardMap, BoardSetup, ChatMessage, ChessClock, GAME_LENGTH, GameOutcome, GameStart,
+	GameStartPlayer, Move, MoveSelection, PlayRequest, PlayResponse, Player, Turn, TurnError,
+	TurnStart, Variant, WinReason,
 };
 use redis::streams::StreamId;
 use redis::{AsyncCommands, SetExpiry, SetOptions};
@@ -44,16 +45,27 @@ pub enum PlaySocketState {
 		elo: f32,
 		elo_range: f32,
 		setup: BoardSetup,
+		#[serde(default)]
+		variant: Variant,
+		// states saved before maps existed were always on the open board
+		#[serde(default = "get_default_map")]
+		map: String,
 		last_message: Option<String>,
 	},
 	Game {
 		game_id: String,
 		my_turn: bool,
 		player: Player,
+		#[serde(default)]
+		variant: Variant,
 		last_message: Option<String>,
 	},
 }
 
+fn get_default_map() -> String {
+	"open".to_string()
+}
+
 impl PlaySocket {
 	pub async fn new(
 		socket: DuplexStream,
@@ -106,7 +118,7 @@ impl PlaySocket {
 		mut self: Self,
 		close_message: &str,
 		allow_reconnect: bool,
-		surrender: bool,
+		forfeit: Option<WinReason>,
 	) {
 		// leave matchmaking queue immidiately to prevent getting matched while disconnected
 		Self::leave_matchmaking_queue(&self.user_id, &mut self.db).await;
@@ -128,12 +140,12 @@ impl PlaySocket {
 				{
 					if new_snowflake == disconnect_snowflake {
 						// cleanup
-						Self::cleanup(state, &user_id, &mut redis, &mut db, surrender).await;
+						Self::cleanup(state, &user_id, &mut redis, &mut db, forfeit).await;
 					}
 				}
 			});
 		} else {
-			Self::cleanup(state, &user_id, &mut redis, &mut db, surrender).await;
+			Self::cleanup(state, &user_id, &mut redis, &mut db, forfeit).await;
 		}
 	}
 	async fn cleanup(
@@ -141,7 +153,7 @@ impl PlaySocket {
 		user_id: &str,
 		redis: &mut Connection<RedisPool>,
 		db: &mut Connection<PostgresPool>,
-		forfeit: bool,
+		forfeit: Option<WinReason>,
 	) {
 		Self::leave_matchmaking_queue(user_id, db).await;
 		let _: usize = redis
@@ -153,12 +165,17 @@ impl PlaySocket {
 			.await
 			.expect("redis error");
 		if let PlaySocketState::Game { game_id, .. } = state {
-			if forfeit {
-				Self::forfeit(redis, &game_id, user_id).await;
+			if let Some(reason) = forfeit {
+				Self::forfeit(redis, &game_id, user_id, reason).await;
 			}
 		}
 	}
-	async fn forfeit(redis: &mut Connection<RedisPool>, game_id: &str, user_id: &str) {
+	async fn forfeit(
+		redis: &mut Connection<RedisPool>,
+		game_id: &str,
+		user_id: &str,
+		reason: WinReason,
+	) {
 		// game service handles game cleanup
 		let _: () = redis
 			.xadd_maxlen(
@@ -167,7 +184,7 @@ impl PlaySocket {
 				"*",
 				&[(
 					"forfeit",
-					&serde_json::to_string(&(&game_id, user_id))
+					&serde_json::to_string(&(&game_id, user_id, reason))
 						.expect("failed to serialize forfeit"),
 				)],
 			)
@@ -207,6 +224,8 @@ impl PlaySocket {
 			elo,
 			elo_range,
 			setup,
+			variant,
+			map,
 			..
 		} = &mut self.state
 		{
@@ -216,13 +235,17 @@ impl PlaySocket {
 				"SELECT id, board_setup FROM matchmaking_players WHERE \
 				elo BETWEEN $1 AND $2 AND \
 				$3 BETWEEN elo - elo_range AND elo + elo_range AND \
-				id != $4 \
+				id != $4 AND \
+				variant = $5 AND \
+				map = $6 \
 				ORDER BY start_time ASC LIMIT 1",
 			)
 			.bind(*elo - *elo_range)
 			.bind(*elo + *elo_range)
 			.bind(*elo)
 			.bind(&self.user_id)
+			.bind(variant.as_str())
+			.bind(&*map)
 			.fetch_one(&mut **self.db)
 			.await
 			{
@@ -232,6 +255,8 @@ impl PlaySocket {
 					Self::enter_matchmaking_queue(
 						&self.user_id,
 						&setup,
+						*variant,
+						map,
 						&mut self.db,
 						*elo,
 						*elo_range,
@@ -273,6 +298,8 @@ impl PlaySocket {
 							game_id: game_id.clone(),
 							white,
 							black,
+							variant: *variant,
+							map: BoardMap::built_in(map).unwrap_or_default(),
 						})
 						.expect("failed to serialize game start"),
 					)],
@@ -284,6 +311,8 @@ impl PlaySocket {
 	async fn enter_matchmaking_queue(
 		user_id: &str,
 		board_setup: &BoardSetup,
+		variant: Variant,
+		map: &str,
 		db: &mut Connection<PostgresPool>,
 		elo: f32,
 		elo_range: f32,
@@ -291,14 +320,16 @@ impl PlaySocket {
 		Self::leave_matchmaking_queue(user_id, db).await;
 		sqlx::query(
 			"INSERT INTO matchmaking_players \
-						(id, elo, elo_range, start_time, board_setup) \
-						VALUES ($1, $2, $3, $4, $5)",
+						(id, elo, elo_range, start_time, board_setup, variant, map) \
+						VALUES ($1, $2, $3, $4, $5, $6, $7)",
 		)
 		.bind(&user_id)
 		.bind(elo)
 		.bind(elo_range)
 		.bind(OffsetDateTime::now_utc())
 		.bind(serde_json::to_string(board_setup).expect("failed to serialize board setup"))
+		.bind(variant.as_str())
+		.bind(map)
 		.execute(&mut ***db)
 		.await
 		.expect("postgres error");
@@ -332,6 +363,7 @@ impl PlaySocket {
 				true => Player::White,
 				false => Player::Black,
 			},
+			variant: game_start.variant,
 			last_message: match &self.state {
 				PlaySocketState::Game { last_message, .. } => last_message.clone(),
 				_ => None,
@@ -339,6 +371,19 @@ impl PlaySocket {
 		};
 		self.send_game_state().await;
 	}
+	// the game service couldnt start the game, so the player has to send a setup again
+	pub async fn game_start_failed(&mut self) {
+		if let PlaySocketState::Matchmaking { .. } = self.state {
+			self.reset_state().await;
+			let _ = self
+				.socket
+				.send(ws::Message::Text(
+					serde_json::to_string(&PlayResponse::InvalidRequest)
+						.expect("failed to serialize invalid request"),
+				))
+				.await;
+		}
+	}
 	pub async fn turn_start(&mut self, turn_start: String) {
 		let turn_start: TurnStart =
 			serde_json::from_str(&turn_start).expect("failed to parse turn start");
@@ -346,10 +391,23 @@ impl PlaySocket {
 			my_turn,
 			player,
 			game_id,
+			variant,
 			..
 		} = &mut self.state
 		{
 			*my_turn = turn_start.turn == *player;
+			// the other player's moves show where their pieces are
+			let (move_pieces, moves) = match variant {
+				Variant::FogOfWar if !*my_turn => (vec![], vec![]),
+				_ => (turn_start.move_pieces, turn_start.moves),
+			};
+			// and their last move where it went
+			let last_move = match variant {
+				Variant::FogOfWar => turn_start
+					.last_move
+					.filter(|played| played.player == *player),
+				_ => turn_start.last_move,
+			};
 			let mut clock: ChessClock = serde_json::from_str(
 				&self
 					.redis
@@ -376,8 +434,13 @@ impl PlaySocket {
 				.send(ws::Message::Text(
 					serde_json::to_string(&PlayResponse::TurnStart {
 						turn: turn_start.turn,
-						move_pieces: turn_start.move_pieces,
-						moves: turn_start.moves,
+						move_pieces,
+						moves,
+						phase: turn_start.phase,
+						checks: turn_start.checks,
+						in_check: turn_start.in_check,
+						checkers: turn_start.checkers,
+						last_move,
 						clock,
 					})
 					.expect("failed to serialize turn start"),
@@ -395,12 +458,36 @@ impl PlaySocket {
 			}
 		}
 	}
-	pub async fn moves_recieved(&mut self, moves: String) {
+	pub async fn invalid_turn(&mut self, invalid_turn: String) {
+		let (turn_player, error): (Player, TurnError) =
+			serde_json::from_str(&invalid_turn).expect("failed to parse invalid turn");
+		if let PlaySocketState::Game { player, .. } = &self.state
+			&& *player == turn_player
+		{
+			let _ = self
+				.socket
+				.send(ws::Message::Text(
+					serde_json::to_string(&PlayResponse::InvalidTurn { error })
+						.expect("failed to serialize invalid turn"),
+				))
+				.await;
+		}
+	}
+	pub async fn moves_recieved(&mut self, moves: String, notation: String) {
+		if let PlaySocketState::Game {
+			variant: Variant::FogOfWar,
+			..
+		} = &self.state
+		{
+			// moves can give away hidden pieces, so the player gets their new view instead
+			self.send_board().await;
+			return;
+		}
 		let moves: Vec<Move> = serde_json::from_str(&moves).expect("failed to parse moves");
 		let _ = self
 			.socket
 			.send(ws::Message::Text(
-				serde_json::to_string(&PlayResponse::Move { moves })
+				serde_json::to_string(&PlayResponse::Move { moves, notation })
 					.expect("failed to serialize moves"),
 			))
 			.await;
@@ -422,17 +509,120 @@ impl PlaySocket {
 			))
 			.await;
 	}
-	pub async fn game_end(&mut self, winner: String) {
+	pub async fn game_end(&mut self, outcome: String) {
+		let outcome: GameOutcome =
+			serde_json::from_str(&outcome).expect("failed to parse game outcome");
 		let _ = self
 			.socket
 			.send(ws::Message::Text(
-				serde_json::to_string(&PlayResponse::End { winner })
+				serde_json::to_string(&PlayResponse::End { outcome })
 					.expect("failed to serialize game end"),
 			))
 			.await;
 	}
+	// the game service checks whether the takeback is allowed
+	async fn takeback(&mut self, field: &str) {
+		if let PlaySocketState::Game { game_id, .. } = &self.state {
+			let _: () = self
+				.redis
+				.xadd_maxlen(
+					"game_requests",
+					redis::streams::StreamMaxlen::Approx(10000),
+					"*",
+					&[(
+						field,
+						&serde_json::to_string(&(game_id, &self.user_id))
+							.expect("failed to serialize takeback"),
+					)],
+				)
+				.await
+				.expect("redis error");
+		}
+	}
+	pub async fn takeback_requested(&mut self, player: String) {
+		let player: Player = serde_json::from_str(&player).expect("failed to parse player");
+		let _ = self
+			.socket
+			.send(ws::Message::Text(
+				serde_json::to_string(&PlayResponse::TakebackRequest { player })
+					.expect("failed to serialize takeback request"),
+			))
+			.await;
+	}
+	pub async fn takeback_recieved(&mut self, board: String) {
+		let board: Board = serde_json::from_str(&board).expect("failed to parse board");
+		let board = self.player_view(board);
+		let _ = self
+			.socket
+			.send(ws::Message::Text(
+				serde_json::to_string(&PlayResponse::Takeback {
+					board: Box::new(board),
+				})
+				.expect("failed to serialize takeback"),
+			))
+			.await;
+	}
+	async fn submit_turn(
+		&mut self,
+		selection: MoveSelection,
+	) -> Option<(&'static str, Option<WinReason>)> {
+		if let PlaySocketState::Game {
+			game_id,
+			my_turn,
+			player,
+			..
+		} = &mut self.state
+		{
+			if !*my_turn {
+				return None;
+			}
+			*my_turn = false;
+			let mut clock: ChessClock = serde_json::from_str(
+				&self
+					.redis
+					.get::<String, String>(format!("clock:{}", game_id))
+					.await
+					.expect("clock doesnt exist"),
+			)
+			.expect("failed to deserialize chess clock");
+			if !clock.player_timer(*player).pause() {
+				return Some(("you ran out of time", Some(WinReason::Timeout)));
+			}
+			let _: () = self
+				.redis
+				.set(
+					format!("clock:{}", game_id),
+					serde_json::to_string(&clock).expect("failed to serialize chess clock"),
+				)
+				.await
+				.expect("failed to set chess clock");
+			let _: () = self
+				.redis
+				.xadd_maxlen(
+					"game_requests",
+					redis::streams::StreamMaxlen::Approx(10000),
+					"*",
+					&[(
+						"turn",
+						serde_json::to_string(&Turn {
+							game_id: game_id.clone(),
+							selection,
+						})
+						.expect("failed to serialize turn"),
+					)],
+				)
+				.await
+				.expect("redis error");
+			self.save_state().await;
+		}
+		None
+	}
 	// handle message from user
-	pub async fn handle_message(&mut self, message: &str) -> Option<&'static str> {
+	// returns the close message and, if the player is forfeiting a game, why
+	pub async fn handle_message(
+		&mut self,
+		message: &str,
+	) -> Option<(&'static str, Option<WinReason>)> {
 		let message: PlayRequest = match serde_json::from_str(message) {
 			Ok(message) => message,
 			Err(_) => return None,
@@ -442,56 +632,28 @@ impl PlaySocket {
 				piece_idx,
 				move_idx,
 			} => {
-				if let PlaySocketState::Game {
-					game_id,
-					my_turn,
-					player,
-					..
-				} = &mut self.state
-				{
-					if !*my_turn {
-						return None;
-					}
-					*my_turn = false;
-					let mut clock: ChessClock = serde_json::from_str(
-						&self
-							.redis
-							.get::<String, String>(format!("clock:{}", game_id))
-							.await
-							.expect("clock doesnt exist"),
-					)
-					.expect("failed to deserialize chess clock");
-					if !clock.player_timer(*player).pause() {
-						return Some("you ran out of time");
-					}
-					let _: () = self
-						.redis
-						.set(
-							format!("clock:{}", game_id),
-							serde_json::to_string(&clock).expect("failed to serialize chess clock"),
-						)
-						.await
-						.expect("failed to set chess clock");
-					let _: () = self
-						.redis
-						.xadd_maxlen(
-							"game_requests",
-							redis::streams::StreamMaxlen::Approx(10000),
-							"*",
-							&[(
-								"turn",
-								serde_json::to_string(&Turn {
-									game_id: game_id.clone(),
-									piece_idx,
-									move_idx,
-								})
-								.expect("failed to serialize turn"),
-							)],
-						)
-						.await
-						.expect("redis error");
-					self.save_state().await;
-				}
+				return self
+					.submit_turn(MoveSelection::Index {
+						piece_idx,
+						move_idx,
+					})
+					.await;
+			}
+			PlayRequest::Move {
+				from,
+				to,
+				promotion,
+			} => {
+				return self
+					.submit_turn(MoveSelection::Coordinates {
+						from,
+						to,
+						promotion,
+					})
+					.await;
+			}
+			PlayRequest::Drop { piece, to } => {
+				return self.submit_turn(MoveSelection::Drop { piece, to }).await;
 			}
 			PlayRequest::ChatMessage { message } => {
 				if message.len() > 1024 {
@@ -534,10 +696,19 @@ impl PlaySocket {
 				}
 			}
 			PlayRequest::ExpandEloRange => self.expand_elo_range().await,
-			PlayRequest::BoardSetup { setup } => {
+			PlayRequest::BoardSetup {
+				setup,
+				variant,
+				map,
+			} => {
 				if let PlaySocketState::WaitingForSetup { .. } = self.state {
-					if !setup.is_valid() {
-						return Some("invalid board setup");
+					let map = map.unwrap_or_else(get_default_map);
+					let Some(board_map) = BoardMap::built_in(&map) else {
+						return Some(("invalid board map", None));
+					};
+					// the setup has to be as wide as the map
+					if !setup.is_valid_for(variant, &board_map) {
+						return Some(("invalid board setup", None));
 					}
 					let elo: f32 = sqlx::query("SELECT elo FROM users WHERE id = $1")
 						.bind(&self.user_id)
@@ -549,6 +720,8 @@ impl PlaySocket {
 						elo,
 						elo_range: 200.0,
 						setup,
+						variant,
+						map,
 						last_message: None,
 					};
 					self.matchmake().await;
@@ -557,9 +730,11 @@ impl PlaySocket {
 			}
 			PlayRequest::Surrender => {
 				if let PlaySocketState::Game { .. } = &self.state {
-					return Some("game surrendered");
+					return Some(("game surrendered", Some(WinReason::Resignation)));
 				}
 			}
+			PlayRequest::TakebackRequest => self.takeback("takeback_request").await,
+			PlayRequest::TakebackAccept => self.takeback("takeback_accept").await,
 		}
 		None
 	}
@@ -580,17 +755,30 @@ impl PlaySocket {
 		if let Some(game_start) = message.get::<String>("game_start") {
 			self.game_start(game_start).await;
 		}
+		if message.get::<String>("game_start_failed").is_some() {
+			self.game_start_failed().await;
+		}
+		if let Some(invalid_turn) = message.get::<String>("invalid_turn") {
+			self.invalid_turn(invalid_turn).await;
+		}
+		if let Some(player) = message.get::<String>("takeback_request") {
+			self.takeback_requested(player).await;
+		}
+		if let Some(board) = message.get::<String>("takeback") {
+			self.takeback_recieved(board).await;
+		}
 		if let Some(turn_start) = message.get::<String>("turn_start") {
 			self.turn_start(turn_start).await;
 		}
 		if let Some(moves) = message.get::<String>("moves") {
-			self.moves_recieved(moves).await;
+			let notation = message.get::<String>("notation").unwrap_or_default();
+			self.moves_recieved(moves, notation).await;
 		}
 		if let Some(chat) = message.get::<String>("chat") {
 			self.chat_recieved(chat).await;
 		}
-		if let Some(winner) = message.get::<String>("end") {
-			self.game_end(winner).await;
+		if let Some(outcome) = message.get::<String>("end") {
+			self.game_end(outcome).await;
 			Some("game ended")
 		} else {
 			None
@@ -600,7 +788,43 @@ impl PlaySocket {
 		self.state = PlaySocketState::WaitingForSetup { last_message: None };
 		self.save_state().await;
 	}
+	// the board with everything the player isnt allowed to see left out
+	fn player_view(&self, board: Board) -> Board {
+		match &self.state {
+			PlaySocketState::Game {
+				variant: Variant::FogOfWar,
+				player,
+				..
+			} => board.fog_view(*player),
+			_ => board,
+		}
+	}
 	pub async fn send_game_state(&mut self) {
+		if !self.send_board().await {
+			return;
+		}
+		if let PlaySocketState::Game { game_id, .. } = &self.state {
+			let full_chat: Vec<ChatMessage> = self
+				.redis
+				.lrange::<String, Vec<String>>(format!("chat:{}", game_id), 0, -1)
+				.await
+				.expect("redis error")
+				.into_iter()
+				.map(|m: String| {
+					serde_json::from_str(&m).expect("failed to deserialize chat message")
+				})
+				.collect();
+			let _ = self
+				.socket
+				.send(ws::Message::Text(
+					serde_json::to_string(&PlayResponse::FullChat { chat: full_chat })
+						.expect("failed to serialize chat messages"),
+				))
+				.await;
+		}
+	}
+	// returns false if the game doesnt exist anymore
+	async fn send_board(&mut self) -> bool {
 		if let PlaySocketState::Game { game_id, .. } = &mut self.state {
 			let board: Board = serde_json::from_str(
 				match &self
@@ -612,7 +836,7 @@ impl PlaySocket {
 					Err(_) => {
 						// game doesnt exist
 						self.reset_state().await;
-						return;
+						return false;
 					}
 				},
 			)
@@ -627,36 +851,24 @@ impl PlaySocket {
 					Err(_) => {
 						// game doesnt exist
 						self.reset_state().await;
-						return;
+						return false;
 					}
 				},
 			)
 			.expect("failed to deserialize chess clock");
+			let board = self.player_view(board);
 			let _ = self
 				.socket
 				.send(ws::Message::Text(
-					serde_json::to_string(&PlayResponse::GameState { board, clock })
-						.expect("failed to serialize game state"),
-				))
-				.await;
-			let full_chat: Vec<ChatMessage> = self
-				.redis
-				.lrange::<String, Vec<String>>(format!("chat:{}", game_id), 0, -1)
-				.await
-				.expect("redis error")
-				.into_iter()
-				.map(|m: String| {
-					serde_json::from_str(&m).expect("failed to deserialize chat message")
-				})
-				.collect();
-			let _ = self
-				.socket
-				.send(ws::Message::Text(
-					serde_json::to_string(&PlayResponse::FullChat { chat: full_chat })
-						.expect("failed to serialize chat messages"),
+					serde_json::to_string(&PlayResponse::GameState {
+						board: Box::new(board),
+						clock,
+					})
+					.expect("failed to serialize game state"),
 				))
 				.await;
 		}
+		true
 	}
 	pub async fn tick(&mut self) -> Option<&'static str> {
 		if let PlaySocketState::Game {
@@ -10,6 +10,9 @@ fi
 if [ -n "${COOKIES_SAME_SITE}" ]; then
 	echo "cookies_same_site = \"${COOKIES_SAME_SITE}\"" >> Rocket.toml
 fi
+if [ -n "${CUSTOM_PIECES_FILE}" ]; then
+	echo "custom_pieces_file = \"${CUSTOM_PIECES_FILE}\"" >> Rocket.toml
+fi
 if [ -n "${LOG_LEVEL}" ]; then
 	echo "log_level = \"${LOG_LEVEL}\"" >> Rocket.toml
 fi
@@ -1,6 +1,7 @@
 use dotenvy::dotenv;
 use duckchess_common::{
-	Board, ChatMessage, ChessClock, GAME_LENGTH, GameStart, Player, Turn, TurnStart,
+	Board, ChatMessage, ChessClock, ClockHistory, CustomPiece, GAME_LENGTH, GameOutcome,
+	GameRecord, GameStart, Player, Turn, TurnPhase, TurnStart, WinReason,
 };
 use redis::{
 	AsyncCommands, SetExpiry, SetOptions,
@@ -23,6 +24,9 @@ use std::{
 	},
 };
 
+// how long finished game records are kept, in seconds
+const RECORD_LIFETIME: u64 = 30 * 24 * 60 * 60;
+
 #[tokio::main]
 async fn main() {
 	dotenv().expect("failed to load .env");
@@ -31,6 +35,10 @@ async fn main() {
 	let autoclaim_time: u64 = get_env_var(&env_vars, "AUTOCLAIM_TIME_MS");
 	let consumer_id: String = get_env_var(&env_vars, "CONSUMER_ID");
 	let consumer_group: String = get_env_var(&env_vars, "CONSUMER_GROUP");
+	// optional, has to be the same file the edge service uses
+	if let Some(path) = env_vars.get("CUSTOM_PIECES_FILE") {
+		CustomPiece::load(path).expect("failed to load custom pieces");
+	}
 
 	let should_exit = Arc::new(AtomicBool::new(false));
 	let should_exit_2 = should_exit.clone();
@@ -166,25 +174,77 @@ async fn process_stream_id(con: &mut MultiplexedConnection, stream_id: &StreamId
 		)
 		.await;
 	}
+	if let Some(takeback) = stream_id.get::<String>("takeback_request") {
+		process_takeback_request(
+			con,
+			serde_json::from_str(&takeback).expect("failed to parse takeback request"),
+		)
+		.await;
+	}
+	if let Some(takeback) = stream_id.get::<String>("takeback_accept") {
+		process_takeback_accept(
+			con,
+			serde_json::from_str(&takeback).expect("failed to parse takeback accept"),
+		)
+		.await;
+	}
 }
 
 async fn process_turn(con: &mut MultiplexedConnection, turn: &str) {
 	let turn: Turn = serde_json::from_str(turn).expect("failed to parse turn");
-	let board_key = format!("board:{}", turn.game_id);
-	let board_str: String = con.get(&board_key).await.expect("failed to get board");
-	let mut board: Board = serde_json::from_str(board_str.as_str()).expect("failed to parse board");
-	let (computed_moves, game_over) = match board.evaluate_turn(&turn) {
-		Some(o) => o,
-		None => return,
+	let Some(mut board) = get_board(con, &turn.game_id).await else {
+		return;
 	};
-	let _: () = con
-		.set_options(
-			&board_key,
-			serde_json::to_string(&board).unwrap(),
-			SetOptions::default().with_expiration(SetExpiry::EX(GAME_LENGTH + 30)),
-		)
+	let player = board.turn;
+	let (computed_moves, outcome) = match board.evaluate_turn(&turn) {
+		Ok(o) => o,
+		Err(error) => {
+			// let the player know and give them their turn back
+			let _: String = con
+				.xadd_maxlen(
+					format!("game:{}", turn.game_id),
+					redis::streams::StreamMaxlen::Approx(1000),
+					"*",
+					&[
+						(
+							"invalid_turn",
+							serde_json::to_string(&(player, error))
+								.expect("failed to serialize turn error"),
+						),
+						(
+							"turn_start",
+							serde_json::to_string(&TurnStart::new(&board))
+								.expect("failed to serialize turn start"),
+						),
+					],
+				)
+				.await
+				.expect("failed to write to game stream");
+			return;
+		}
+	};
+	set_board(con, &board).await;
+	// the player who moved has stopped their timer, so this is where the next turn starts
+	let clock: Option<String> = con
+		.get(format!("clock:{}", turn.game_id))
+		.await
+		.expect("failed to get clock");
+	let history: Option<String> = con
+		.get(format!("clock_history:{}", turn.game_id))
+		.await
+		.expect("failed to get clock history");
+	if let (Some(clock), Some(history)) = (clock, history) {
+		let clock: ChessClock = serde_json::from_str(&clock).expect("failed to parse clock");
+		let mut history: ClockHistory =
+			serde_json::from_str(&history).expect("failed to parse clock history");
+		history.push(&clock);
+		set_clock_history(con, &turn.game_id, &history).await;
+	}
+	// a pending takeback request was for the position before this move
+	let _: i32 = con
+		.del(format!("takeback:{}", turn.game_id))
 		.await
-		.expect("failed to set board");
+		.expect("failed to delete takeback request");
 	let _: String = con
 		.xadd_maxlen(
 			format!("game:{}", turn.game_id),
@@ -192,59 +252,133 @@ async fn process_turn(con: &mut MultiplexedConnection, turn: &str) {
 			"*",
 			&[
 				("moves", serde_json::to_string(&computed_moves).unwrap()),
+				(
+					"notation",
+					board
+						.history
+						.last()
+						.map(|played| played.notation.clone())
+						.unwrap_or_default(),
+				),
 				(
 					"turn_start",
-					serde_json::to_string(&TurnStart {
-						turn: board.turn,
-						move_pieces: board.move_pieces.clone(),
-						moves: board.moves.clone(),
-					})
-					.expect("failed to serialize turn start"),
+					serde_json::to_string(&TurnStart::new(&board))
+						.expect("failed to serialize turn start"),
 				),
 			],
 		)
 		.await
 		.expect("Failed to write to moves stream");
-	if game_over {
-		end_game(con, &board, &board.get_not_turn_player_id()).await;
+	if let Some(outcome) = outcome {
+		end_game(con, &board, &outcome).await;
 	}
 }
 
+// the board together with its undo stack, which is kept apart so it never reaches the clients
+async fn get_board(con: &mut MultiplexedConnection, game_id: &str) -> Option<Board> {
+	let board: Option<String> = con
+		.get(format!("board:{}", game_id))
+		.await
+		.expect("failed to get board");
+	let mut board: Board = serde_json::from_str(&board?).expect("failed to parse board");
+	let undo: Option<String> = con
+		.get(format!("undo:{}", game_id))
+		.await
+		.expect("failed to get undo stack");
+	if let Some(undo) = undo {
+		board.takebacks = serde_json::from_str(&undo).expect("failed to parse undo stack");
+	}
+	Some(board)
+}
+
+async fn set_board(con: &mut MultiplexedConnection, board: &Board) {
+	for (key, value) in [
+		(
+			format!("board:{}", board.id),
+			serde_json::to_string(board).expect("failed to serialize board"),
+		),
+		(
+			format!("undo:{}", board.id),
+			serde_json::to_string(&board.takebacks).expect("failed to serialize undo stack"),
+		),
+	] {
+		let _: () = con
+			.set_options(
+				key,
+				value,
+				SetOptions::default().with_expiration(SetExpiry::EX(GAME_LENGTH + 30)),
+			)
+			.await
+			.expect("failed to set board");
+	}
+}
+
+async fn set_clock_history(con: &mut MultiplexedConnection, game_id: &str, history: &ClockHistory) {
+	let _: () = con
+		.set_options(
+			format!("clock_history:{}", game_id),
+			serde_json::to_string(history).expect("failed to serialize clock history"),
+			SetOptions::default().with_expiration(SetExpiry::EX(GAME_LENGTH + 30)),
+		)
+		.await
+		.expect("failed to set clock history");
+}
+
 async fn process_game_start(con: &mut MultiplexedConnection, game_start_str: &str) {
 	let game_start: GameStart =
 		serde_json::from_str(game_start_str).expect("failed to parse game start");
-	let board_key = format!("board:{}", game_start.game_id);
 	let game_id = game_start.game_id.clone();
 	let white_id = game_start.white.id.clone();
 	let black_id = game_start.black.id.clone();
+	// the edge service only lets through pieces it knows, so this means the two services loaded
+	// different custom pieces. the unknown pieces couldnt move, so the game isnt started and
+	// both players are sent back to the setup, they already left the matchmaking queue
+	if game_start.white.setup.has_unknown_pieces() || game_start.black.setup.has_unknown_pieces() {
+		eprintln!(
+			"game {} uses custom pieces that arent loaded, check CUSTOM_PIECES_FILE",
+			game_id
+		);
+		for user_id in [&white_id, &black_id] {
+			let _: String = con
+				.xadd_maxlen(
+					format!("user:{}", user_id),
+					redis::streams::StreamMaxlen::Approx(1000),
+					"*",
+					&[("game_start_failed", &game_id)],
+				)
+				.await
+				.expect("failed to write to user stream");
+		}
+		return;
+	}
 	let board = Board::new(game_start);
 	let expire_time = GAME_LENGTH + 30;
+	set_board(con, &board).await;
+	let clock = ChessClock::new();
 	let _: () = con
 		.set_options(
-			&board_key,
-			serde_json::to_string(&board).expect("failed to serialize board"),
+			format!("clock:{}", game_id),
+			serde_json::to_string(&clock).expect("failed to serialize clock"),
 			SetOptions::default().with_expiration(SetExpiry::EX(expire_time)),
 		)
 		.await
-		.expect("failed to set board");
+		.expect("failed to set clock");
+	set_clock_history(con, &game_id, &ClockHistory::new(&clock)).await;
+	// kept for the game record
 	let _: () = con
 		.set_options(
-			format!("clock:{}", game_id),
-			serde_json::to_string(&ChessClock::new()).expect("failed to serialize clock"),
+			format!("game_start:{}", game_id),
+			game_start_str,
 			SetOptions::default().with_expiration(SetExpiry::EX(expire_time)),
 		)
 		.await
-		.expect("failed to set clock");
+		.expect("failed to set game start");
 	let message = [
 		("game_start", game_start_str),
 		(
 			"turn_start",
-			&serde_json::to_string(&TurnStart {
-				turn: Player::White,
-				move_pieces: board.move_pieces.clone(),
-				moves: board.moves.clone(),
-			})
-			.expect("failed to serialize turn start"),
+			&serde_json::to_string(&TurnStart::new(&board))
+				.expect("failed to serialize turn start"),
 		),
 	];
 	let _: String = con
@@ -278,54 +412,221 @@ async fn process_game_start(con: &mut MultiplexedConnection, game_start_str: &st
 		)
 		.await
 		.expect("failed to write to user stream");
-	if board.moves.is_empty() {
-		end_game(con, &board, &board.white_player).await;
+	if let Some(outcome) = board.outcome() {
+		end_game(con, &board, &outcome).await;
 	}
 }
 
-async fn process_forfeit(con: &mut MultiplexedConnection, (game_id, player_id): (String, String)) {
-	let board_key = format!("board:{}", game_id);
-	let board_str: String = match con.get(&board_key).await {
-		Ok(board_str) => board_str,
-		Err(_) => return,
+async fn process_forfeit(
+	con: &mut MultiplexedConnection,
+	(game_id, player_id, reason): (String, String, WinReason),
+) {
+	let Some(board) = get_board(con, &game_id).await else {
+		return;
 	};
-	let board: Board = serde_json::from_str(board_str.as_str()).expect("failed to parse board");
 	let winner = if board.white_player == player_id {
-		board.black_player.clone()
+		Player::Black
 	} else {
-		board.white_player.clone()
+		Player::White
 	};
-	end_game(con, &board, &winner).await;
+	end_game(con, &board, &GameOutcome::Win { winner, reason }).await;
 }
 
-async fn end_game(con: &mut MultiplexedConnection, board: &Board, winner: &str) {
+// how many turns need to be taken back for it to be the start of the player's turn again
+fn takeback_turns(board: &Board, player: Player) -> Option<usize> {
+	let mut board = board.clone();
+	let mut turns = 0;
+	loop {
+		board.takeback()?;
+		turns += 1;
+		if board.turn == player && board.phase == TurnPhase::Piece {
+			return Some(turns);
+		}
+	}
+}
+
+fn player_of(board: &Board, player_id: &str) -> Option<Player> {
+	if board.white_player == player_id {
+		Some(Player::White)
+	} else if board.black_player == player_id {
+		Some(Player::Black)
+	} else {
+		None
+	}
+}
+
+async fn process_takeback_request(
+	con: &mut MultiplexedConnection,
+	(game_id, player_id): (String, String),
+) {
+	let Some(board) = get_board(con, &game_id).await else {
+		return;
+	};
+	let Some(player) = player_of(&board, &player_id) else {
+		return;
+	};
+	if takeback_turns(&board, player).is_none() {
+		return;
+	}
+	let _: () = con
+		.set_options(
+			format!("takeback:{}", game_id),
+			&player_id,
+			SetOptions::default().with_expiration(SetExpiry::EX(GAME_LENGTH + 30)),
+		)
+		.await
+		.expect("failed to set takeback request");
+	let _: String = con
+		.xadd_maxlen(
+			format!("game:{}", game_id),
+			redis::streams::StreamMaxlen::Approx(1000),
+			"*",
+			&[(
+				"takeback_request",
+				serde_json::to_string(&player).expect("failed to serialize player"),
+			)],
+		)
+		.await
+		.expect("failed to write to game stream");
+}
+
+async fn process_takeback_accept(
+	con: &mut MultiplexedConnection,
+	(game_id, player_id): (String, String),
+) {
+	let takeback_key = format!("takeback:{}", game_id);
+	let requester: Option<String> = con
+		.get(&takeback_key)
+		.await
+		.expect("failed to get takeback request");
+	// only the opponent of the requester can accept
+	match requester {
+		Some(requester) if requester != player_id => {}
+		_ => return,
+	}
+	let _: i32 = con
+		.del(&takeback_key)
+		.await
+		.expect("failed to delete takeback request");
+	let Some(mut board) = get_board(con, &game_id).await else {
+		return;
+	};
+	let Some(accepter) = player_of(&board, &player_id) else {
+		return;
+	};
+	let Some(turns) = takeback_turns(&board, !accepter) else {
+		return;
+	};
+	// stop the clock of the player on turn, turn_start starts the right one again
+	let clock_key = format!("clock:{}", game_id);
+	let clock: Option<String> = con.get(&clock_key).await.expect("failed to get clock");
+	let Some(clock) = clock else {
+		return;
+	};
+	let mut clock: ChessClock = serde_json::from_str(&clock).expect("failed to parse clock");
+	if !clock.player_timer(board.turn).pause() {
+		// they ran out of time, which their socket will notice
+		return;
+	}
+	// both players get back the time they spent on the turns that are taken back
+	let history: Option<String> = con
+		.get(format!("clock_history:{}", game_id))
+		.await
+		.expect("failed to get clock history");
+	if let Some(history) = history {
+		let mut history: ClockHistory =
+			serde_json::from_str(&history).expect("failed to parse clock history");
+		if history.take_back(turns, &mut clock) {
+			set_clock_history(con, &game_id, &history).await;
+		}
+	}
+	let _: () = con
+		.set_options(
+			&clock_key,
+			serde_json::to_string(&clock).expect("failed to serialize clock"),
+			SetOptions::default().with_expiration(SetExpiry::EX(GAME_LENGTH + 30)),
+		)
+		.await
+		.expect("failed to set clock");
+	for _ in 0..turns {
+		board.takeback();
+	}
+	set_board(con, &board).await;
+	let _: String = con
+		.xadd_maxlen(
+			format!("game:{}", game_id),
+			redis::streams::StreamMaxlen::Approx(1000),
+			"*",
+			&[
+				(
+					"takeback",
+					serde_json::to_string(&board).expect("failed to serialize board"),
+				),
+				(
+					"turn_start",
+					serde_json::to_string(&TurnStart::new(&board))
+						.expect("failed to serialize turn start"),
+				),
+			],
+		)
+		.await
+		.expect("failed to write to game stream");
+}
+
+async fn end_game(con: &mut MultiplexedConnection, board: &Board, outcome: &GameOutcome) {
 	let chat_message = ChatMessage {
 		id: "".to_string(),
-		message: format!(
-			"{} wins",
-			if board.white_player == winner {
-				"white"
-			} else {
-				"black"
-			}
-		),
+		message: outcome.to_string(),
 	};
 	let message = serde_json::to_string(&chat_message).expect("failed to serialize chat message");
+	let outcome_str = serde_json::to_string(outcome).expect("failed to serialize outcome");
 	let _: () = con
 		.xadd_maxlen(
 			format!("game:{}", board.id),
 			redis::streams::StreamMaxlen::Approx(1000),
 			"*",
-			&[("chat", message.as_str()), ("end", winner)],
+			&[("chat", message.as_str()), ("end", outcome_str.as_str())],
 		)
 		.await
 		.expect("failed to write to game stream");
+	save_record(con, board, outcome).await;
 	for key in [
 		format!("board:{}", board.id),
+		format!("undo:{}", board.id),
+		format!("game_start:{}", board.id),
+		format!("takeback:{}", board.id),
 		format!("game:{}", board.id),
 		format!("chat:{}", board.id),
 		format!("clock:{}", board.id),
+		format!("clock_history:{}", board.id),
 	] {
 		let _: i32 = con.expire(key, 30).await.expect("failed to expire key");
 	}
 }
+
+// finished games are kept around as pgn
+async fn save_record(con: &mut MultiplexedConnection, board: &Board, outcome: &GameOutcome) {
+	let game_start: Option<String> = con
+		.get(format!("game_start:{}", board.id))
+		.await
+		.expect("failed to get game start");
+	let Some(game_start) = game_start else {
+		return;
+	};
+	let game_start: GameStart =
+		serde_json::from_str(&game_start).expect("failed to parse game start");
+	let clock: Option<String> = con
+		.get(format!("clock:{}", board.id))
+		.await
+		.expect("failed to get clock");
+	let clock = clock.map(|clock| serde_json::from_str(&clock).expect("failed to parse clock"));
+	let record = GameRecord::new(game_start, board, outcome.clone(), clock);
+	let _: () = con
+		.set_options(
+			format!("pgn:{}", board.id),
+			record.to_pgn(),
+			SetOptions::default().with_expiration(SetExpiry::EX(RECORD_LIFETIME)),
+		)
+		.await
+		.expect("failed to set pgn");
+}
@@ -4,5 +4,8 @@ echo "REDIS_URL=${REDIS_URL}" > .env && \
 	echo "AUTOCLAIM_TIME_MS=${AUTOCLAIM_TIME_MS}" >> .env && \
 	echo "CONSUMER_ID=${CONSUMER_ID}" >> .env && \
 	echo "CONSUMER_GROUP=${CONSUMER_GROUP}" >> .env
+if [ -n "${CUSTOM_PIECES_FILE}" ]; then
+	echo "CUSTOM_PIECES_FILE=${CUSTOM_PIECES_FILE}" >> .env
+fi
 
 exec "${BINARY_PATH}"
@@ -8,5 +8,7 @@ CREATE TABLE matchmaking_players (
 	elo REAL NOT NULL DEFAULT 1500,
 	elo_range REAL NOT NULL DEFAULT 1500,
 	start_time TIMESTAMP NOT NULL DEFAULT NOW(),
-	board_setup TEXT NOT NULL
+	board_setup TEXT NOT NULL,
+	variant TEXT NOT NULL DEFAULT 'standard',
+	map TEXT NOT NULL DEFAULT 'open'
 );